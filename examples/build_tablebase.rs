@@ -0,0 +1,60 @@
+//! Warms up a tablebase for the default rules on the standard board and writes it out, by
+//! default to `assets/tablebase.bin` where the web app loads it from. The table holds only the
+//! small positions met while counting the solutions of the generated puzzles, not every one.
+//!
+//! ```bash
+//! cargo run --release --example build_tablebase -- [path] [puzzles]
+//! ```
+
+use std::sync::Arc;
+
+use solo_chess_solver::board::Shape;
+use solo_chess_solver::generator::generate;
+use solo_chess_solver::piece::PieceType;
+use solo_chess_solver::rules::Rules;
+use solo_chess_solver::solver::tablebase::Tablebase;
+use solo_chess_solver::solver::{SolverConfig, count_solutions_with};
+
+const MIX: &[PieceType] = &[
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Knight,
+    PieceType::Pawn,
+    PieceType::Pawn,
+];
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or("assets/tablebase.bin".to_string());
+    let puzzles: u64 = args.next().map_or(40, |n| n.parse().expect("puzzle count"));
+
+    let rules = Rules::default();
+    let tablebase = Arc::new(Tablebase::new(Shape::STANDARD, &rules));
+    let config = SolverConfig {
+        max_nodes: Some(1_000_000),
+        tablebase: Some(tablebase.clone()),
+        ..SolverConfig::default()
+    };
+    // Counting every solution walks the whole tree, so it meets every small position the
+    // puzzle can lead to.
+    for seed in 0..puzzles {
+        let board = generate(5 + seed as usize % 8, MIX, &rules, seed);
+        count_solutions_with(&board, &config);
+    }
+
+    if tablebase.is_full() {
+        eprintln!("warning: the table filled up, so later positions were left out");
+    }
+    let bytes = tablebase.to_bytes();
+    std::fs::write(&path, &bytes).expect("write the tablebase");
+    println!(
+        "{} positions, {} bytes, written to {path}",
+        tablebase.len(),
+        bytes.len()
+    );
+}
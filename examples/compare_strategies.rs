@@ -0,0 +1,115 @@
+//! Runs search strategies over the same generated puzzles and prints how each one did. With the
+//! `parallel` feature, the multi-threaded depth-first search gets a row too.
+//!
+//! ```bash
+//! cargo run --release --example compare_strategies -- [--strategy <name|all>] [--puzzles <n>]
+//! cargo run --release --features parallel --example compare_strategies
+//! ```
+
+use std::time::Duration;
+
+use solo_chess_solver::generator::generate;
+use solo_chess_solver::piece::PieceType;
+use solo_chess_solver::rules::Rules;
+use solo_chess_solver::solver::strategy::{Solver, Strategy};
+use solo_chess_solver::solver::{SolveOutcome, SolverConfig};
+
+const MIX: &[PieceType] = &[
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Knight,
+    PieceType::Pawn,
+    PieceType::Pawn,
+];
+
+fn main() {
+    let mut strategies = Strategy::ALL.to_vec();
+    let mut puzzles = 40;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| usage(&format!("{arg} needs a value")))
+        };
+        match arg.as_str() {
+            "--strategy" => match value().as_str() {
+                "all" => strategies = Strategy::ALL.to_vec(),
+                name => match name.parse::<Strategy>() {
+                    Ok(strategy) => strategies = vec![strategy],
+                    Err(e) => usage(&e.to_string()),
+                },
+            },
+            "--puzzles" => {
+                puzzles = value()
+                    .parse()
+                    .unwrap_or_else(|_| usage("bad puzzle count"))
+            }
+            _ => usage(&format!("unknown option {arg}")),
+        }
+    }
+
+    let rules = Rules::default();
+    let corpus: Vec<_> = (0..puzzles)
+        .map(|seed| generate(6 + seed as usize % 9, MIX, &rules, seed))
+        .collect();
+    let config = SolverConfig {
+        max_nodes: Some(1_000_000),
+        ..SolverConfig::default()
+    };
+
+    println!(
+        "{:<20} {:>7} {:>8} {:>12} {:>10}",
+        "strategy", "solved", "aborted", "expanded", "time"
+    );
+    for strategy in strategies {
+        let (mut solved, mut aborted, mut nodes, mut elapsed) = (0, 0, 0, Duration::ZERO);
+        for board in &corpus {
+            let (outcome, stats) = strategy.solve(board, &config);
+            match outcome {
+                SolveOutcome::Solved(_) => solved += 1,
+                SolveOutcome::Aborted { .. } => aborted += 1,
+                _ => {}
+            }
+            nodes += stats.expanded;
+            elapsed += stats.elapsed;
+        }
+        println!(
+            "{:<20} {solved:>7} {aborted:>8} {nodes:>12} {:>9.1?}",
+            strategy.name(),
+            elapsed
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        use solo_chess_solver::solver::parallel::solve_parallel;
+        use std::time::Instant;
+
+        let (mut solved, mut aborted) = (0, 0);
+        let start = Instant::now();
+        for board in &corpus {
+            match solve_parallel(board, &config, 0) {
+                SolveOutcome::Solved(_) => solved += 1,
+                SolveOutcome::Aborted { .. } => aborted += 1,
+                _ => {}
+            }
+        }
+        println!(
+            "{:<20} {solved:>7} {aborted:>8} {:>12} {:>9.1?}",
+            "parallel",
+            "-",
+            start.elapsed()
+        );
+    }
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("{error}");
+    eprintln!("usage: compare_strategies [--strategy <name|all>] [--puzzles <n>]");
+    std::process::exit(2);
+}
@@ -0,0 +1,35 @@
+//! Writes a generated puzzle for the default rules as a DIMACS CNF file, for cross-checking
+//! with an external SAT solver. The comment lines at the top list the pieces and what every
+//! capture variable stands for.
+//!
+//! ```bash
+//! cargo run --release --example export_cnf -- [pieces] [seed] > puzzle.cnf
+//! ```
+
+use solo_chess_solver::generator::generate;
+use solo_chess_solver::piece::PieceType;
+use solo_chess_solver::rules::Rules;
+use solo_chess_solver::solver::sat::encode;
+
+const MIX: &[PieceType] = &[
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Knight,
+    PieceType::Pawn,
+    PieceType::Pawn,
+];
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let pieces: usize = args.next().map_or(12, |n| n.parse().expect("piece count"));
+    let seed: u64 = args.next().map_or(0, |n| n.parse().expect("seed"));
+
+    let rules = Rules::default();
+    let board = generate(pieces, MIX, &rules, seed);
+    print!("{}", encode(&board, &rules).to_dimacs());
+}
@@ -0,0 +1,332 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use std::sync::OnceLock;
+
+use crate::board::{SIZE, STRIDE, Shape};
+use crate::piece::{Piece, PieceType};
+use crate::rules::PawnDirection;
+
+const WORDS: usize = SIZE / 64;
+
+/// A set of squares. Square `(r, c)` is bit `r * STRIDE + c` whatever the board's shape, so
+/// squares off the board are simply never set.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct Bitboard([u64; WORDS]);
+
+impl Bitboard {
+    pub const EMPTY: Self = Self([0; WORDS]);
+
+    pub fn square(sq: usize) -> Self {
+        let mut words = [0; WORDS];
+        words[sq / 64] = 1 << (sq % 64);
+        Self(words)
+    }
+
+    pub fn contains(self, sq: usize) -> bool {
+        self.0[sq / 64] & 1 << (sq % 64) != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0.iter().fold(0, |acc, w| acc | w) == 0
+    }
+
+    pub fn count(self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// The lowest square in the set.
+    pub fn first(self) -> Option<usize> {
+        let i = self.0.iter().position(|&w| w != 0)?;
+        Some(i * 64 + self.0[i].trailing_zeros() as usize)
+    }
+
+    /// The highest square in the set.
+    pub fn last(self) -> Option<usize> {
+        let i = self.0.iter().rposition(|&w| w != 0)?;
+        Some(i * 64 + 63 - self.0[i].leading_zeros() as usize)
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] & rhs.0[i]))
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] | rhs.0[i]))
+    }
+}
+
+impl BitXor for Bitboard {
+    type Output = Self;
+    fn bitxor(self, rhs: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] ^ rhs.0[i]))
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+    fn not(self) -> Self {
+        Self(self.0.map(|w| !w))
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = *self & rhs;
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = *self | rhs;
+    }
+}
+
+impl BitXorAssign for Bitboard {
+    fn bitxor_assign(&mut self, rhs: Self) {
+        *self = *self ^ rhs;
+    }
+}
+
+impl FromIterator<usize> for Bitboard {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(Self::EMPTY, |acc, sq| acc | Self::square(sq))
+    }
+}
+
+/// Attack tables for every piece type on one board shape, built once from
+/// [`PieceType::movement`].
+struct Tables {
+    /// Every direction some piece rides or hops along.
+    lines: Vec<Line>,
+    /// Indexed by pawn direction, then piece type.
+    kinds: Vec<Kind>,
+}
+
+/// One direction of travel, with the squares strictly beyond each square along it.
+struct Line {
+    step: (i32, i32),
+    /// Change in square index per step. Square indices along a line only ever go up or only
+    /// ever go down, so the nearest piece is found with a single bit scan.
+    delta: i32,
+    rays: [Bitboard; SIZE],
+}
+
+/// Tables for one piece type, facing one way.
+struct Kind {
+    leaps: [Bitboard; SIZE],
+    rides: Vec<usize>,
+    hops: Vec<usize>,
+    /// Squares a piece could land on within k captures, for k up to [`Piece::UNLIMITED`].
+    reach: [[Bitboard; SIZE]; Piece::UNLIMITED + 1],
+}
+
+const SHAPES: usize = Shape::MAX - Shape::MIN + 1;
+
+fn tables(shape: Shape) -> &'static Tables {
+    static TABLES: [[OnceLock<Tables>; SHAPES]; SHAPES] =
+        [const { [const { OnceLock::new() }; SHAPES] }; SHAPES];
+    TABLES[shape.rows - Shape::MIN][shape.cols - Shape::MIN].get_or_init(|| Tables::build(shape))
+}
+
+impl Tables {
+    fn build(shape: Shape) -> Self {
+        let mut lines: Vec<Line> = Vec::new();
+        let mut kinds = Vec::new();
+        for pawns in [PawnDirection::Up, PawnDirection::Down] {
+            for piece_type in PieceType::ALL {
+                let movement = piece_type.movement();
+                // Movement is written for pawns capturing toward row 0.
+                let facing = |&(dr, dc): &(i32, i32)| match pawns {
+                    PawnDirection::Up => (dr, dc),
+                    PawnDirection::Down => (-dr, dc),
+                };
+                let mut line_of = |d: (i32, i32)| match lines.iter().position(|l| l.step == d) {
+                    Some(i) => i,
+                    None => {
+                        lines.push(Line::new(shape, d));
+                        lines.len() - 1
+                    }
+                };
+                kinds.push(Kind {
+                    leaps: std::array::from_fn(|sq| {
+                        movement
+                            .leaps
+                            .iter()
+                            .map(facing)
+                            .filter_map(|(dr, dc)| offset(shape, sq, dr, dc))
+                            .collect()
+                    }),
+                    rides: movement
+                        .rides
+                        .iter()
+                        .map(facing)
+                        .map(&mut line_of)
+                        .collect(),
+                    hops: movement.hops.iter().map(facing).map(&mut line_of).collect(),
+                    reach: [[Bitboard::EMPTY; SIZE]; Piece::UNLIMITED + 1],
+                });
+            }
+        }
+
+        let mut tables = Self { lines, kinds };
+        for k in 0..tables.kinds.len() {
+            let potential: Vec<Bitboard> = (0..SIZE)
+                .map(|sq| tables.potential(&tables.kinds[k], sq))
+                .collect();
+            tables.kinds[k].reach = reach_of(&potential);
+        }
+        tables
+    }
+
+    fn kind(&self, piece_type: PieceType, pawns: PawnDirection) -> &Kind {
+        let facing = match pawns {
+            PawnDirection::Up => 0,
+            PawnDirection::Down => 1,
+        };
+        &self.kinds[facing * PieceType::ALL.len() + piece_type as usize]
+    }
+
+    /// Squares `kind` could capture on from `sq`, whatever stands in between.
+    fn potential(&self, kind: &Kind, sq: usize) -> Bitboard {
+        let rides = kind.rides.iter().map(|&l| self.lines[l].rays[sq]);
+        // A hop needs a piece to jump over, so it never lands on the nearest square.
+        let hops = kind.hops.iter().map(|&l| {
+            let ray = self.lines[l].rays[sq];
+            match self.lines[l].nearest(ray) {
+                Some(near) => ray & !Bitboard::square(near),
+                None => Bitboard::EMPTY,
+            }
+        });
+        rides.chain(hops).fold(kind.leaps[sq], |acc, bb| acc | bb)
+    }
+}
+
+fn reach_of(potential: &[Bitboard]) -> [[Bitboard; SIZE]; Piece::UNLIMITED + 1] {
+    let step = |from: Bitboard| squares(from).fold(Bitboard::EMPTY, |acc, u| acc | potential[u]);
+    let mut by_moves = [[Bitboard::EMPTY; SIZE]; Piece::UNLIMITED + 1];
+    for moves in 1..=Piece::MAX_MOVE_LEFT {
+        by_moves[moves] =
+            std::array::from_fn(|sq| step(by_moves[moves - 1][sq] | Bitboard::square(sq)));
+    }
+    by_moves[Piece::UNLIMITED] = std::array::from_fn(|sq| {
+        let mut seen = by_moves[Piece::MAX_MOVE_LEFT][sq];
+        loop {
+            let next = seen | step(seen);
+            if next == seen {
+                break seen;
+            }
+            seen = next;
+        }
+    });
+    by_moves
+}
+
+impl Line {
+    fn new(shape: Shape, (dr, dc): (i32, i32)) -> Self {
+        Self {
+            step: (dr, dc),
+            delta: dr * STRIDE as i32 + dc,
+            rays: std::array::from_fn(|sq| {
+                let mut ray = Bitboard::EMPTY;
+                let mut cur = sq;
+                while let Some(next) = offset(shape, cur, dr, dc) {
+                    ray |= Bitboard::square(next);
+                    cur = next;
+                }
+                ray
+            }),
+        }
+    }
+
+    /// The square in `bb`, which must lie on one of this line's rays, closest to the start.
+    fn nearest(&self, bb: Bitboard) -> Option<usize> {
+        if self.delta > 0 {
+            bb.first()
+        } else {
+            bb.last()
+        }
+    }
+
+    /// Squares up to and including the first occupied one.
+    fn ride(&self, sq: usize, occupied: Bitboard) -> Bitboard {
+        let ray = self.rays[sq];
+        match self.nearest(ray & occupied) {
+            Some(blocker) => ray ^ self.rays[blocker],
+            None => ray,
+        }
+    }
+
+    /// The square just beyond the first occupied one, if there is such a square.
+    fn hop(&self, sq: usize, occupied: Bitboard) -> Bitboard {
+        self.nearest(self.rays[sq] & occupied)
+            .and_then(|hurdle| self.nearest(self.rays[hurdle]))
+            .map_or(Bitboard::EMPTY, Bitboard::square)
+    }
+}
+
+pub fn bit(r: usize, c: usize) -> Bitboard {
+    Bitboard::square(r * STRIDE + c)
+}
+
+/// Iterates the square indices set in `bb`, lowest first.
+pub fn squares(bb: Bitboard) -> impl Iterator<Item = usize> {
+    bb.0.into_iter().enumerate().flat_map(|(i, mut w)| {
+        std::iter::from_fn(move || {
+            if w == 0 {
+                return None;
+            }
+            let sq = i * 64 + w.trailing_zeros() as usize;
+            w &= w - 1;
+            Some(sq)
+        })
+    })
+}
+
+/// Squares a piece on `sq` attacks, given every occupied square. A rider's attack stops at
+/// (and includes) the first occupied square along each line.
+pub fn attacks(
+    shape: Shape,
+    piece_type: PieceType,
+    sq: usize,
+    occupied: Bitboard,
+    pawns: PawnDirection,
+) -> Bitboard {
+    let tables = tables(shape);
+    let kind = tables.kind(piece_type, pawns);
+    let mut attacks = kind.leaps[sq];
+    for &l in &kind.rides {
+        attacks |= tables.lines[l].ride(sq, occupied);
+    }
+    for &l in &kind.hops {
+        attacks |= tables.lines[l].hop(sq, occupied);
+    }
+    attacks
+}
+
+/// Squares a piece of `piece_type` on `sq` could land on within `moves` captures, if every
+/// square it wanted to capture on held a piece and nothing ever stood in between.
+/// [`Piece::UNLIMITED`] gives every square it could ever land on.
+pub fn reach(
+    shape: Shape,
+    piece_type: PieceType,
+    sq: usize,
+    moves: usize,
+    pawns: PawnDirection,
+) -> Bitboard {
+    tables(shape).kind(piece_type, pawns).reach[moves.min(Piece::UNLIMITED)][sq]
+}
+
+fn offset(shape: Shape, sq: usize, dr: i32, dc: i32) -> Option<usize> {
+    let r = (sq / STRIDE) as i32 + dr;
+    let c = (sq % STRIDE) as i32 + dc;
+    if r < 0 || r >= shape.rows as i32 || c < 0 || c >= shape.cols as i32 {
+        return None;
+    }
+    Some(r as usize * STRIDE + c as usize)
+}
@@ -1,30 +1,127 @@
-use crate::piece::Piece;
+use std::hash::{Hash, Hasher};
 
-const N: usize = 8;
-const SIZE: usize = N * N;
+use crate::bitboard::{Bitboard, squares};
+use crate::piece::{Piece, PieceType};
+use crate::rules::Rules;
+use crate::zobrist;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// Square indices leave room for the widest board, so `(r, c)` is square `r * STRIDE + c`
+/// whatever the shape.
+pub(crate) const STRIDE: usize = Shape::MAX;
+pub(crate) const SIZE: usize = STRIDE * Shape::MAX;
+
+/// Rows and columns of a rectangular board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Shape {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl Shape {
+    pub const MIN: usize = 3;
+    pub const MAX: usize = 16;
+    pub const STANDARD: Shape = Shape { rows: 8, cols: 8 };
+
+    /// A board `rows` by `cols`, if both lie between [`Shape::MIN`] and [`Shape::MAX`].
+    pub fn new(rows: usize, cols: usize) -> Option<Shape> {
+        let fits = |n| (Self::MIN..=Self::MAX).contains(&n);
+        (fits(rows) && fits(cols)).then_some(Shape { rows, cols })
+    }
+
+    pub fn contains(&self, r: usize, c: usize) -> bool {
+        r < self.rows && c < self.cols
+    }
+}
+
+impl Default for Shape {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+/// The board as occupancy bitboards: one per piece type and one per remaining-move count.
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Board {
-    cells: [Option<Piece>; SIZE],
+    shape: Shape,
+    occupied: Bitboard,
+    by_type: [Bitboard; PieceType::ALL.len()],
+    by_move_left: [Bitboard; Piece::UNLIMITED + 1],
+    key: u64,
+    /// Key of the same position reflected across the middle of the board, left to right.
+    mirror_key: u64,
+}
+
+impl Hash for Board {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.key);
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Board {
+    /// An empty 8x8 board.
     pub fn new() -> Self {
+        Self::with_shape(Shape::STANDARD)
+    }
+
+    pub fn with_shape(shape: Shape) -> Self {
         Self {
-            cells: [None; SIZE],
+            shape,
+            occupied: Bitboard::EMPTY,
+            by_type: [Bitboard::EMPTY; PieceType::ALL.len()],
+            by_move_left: [Bitboard::EMPTY; Piece::UNLIMITED + 1],
+            key: 0,
+            mirror_key: 0,
+        }
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    /// The same position on a board of another shape. Pieces that no longer fit are dropped.
+    pub fn resized(&self, shape: Shape) -> Board {
+        let mut board = Board::with_shape(shape);
+        for (r, c, p) in self.pieces().filter(|&(r, c, _)| shape.contains(r, c)) {
+            board.set_cell(r, c, p);
         }
+        board
     }
 
     pub fn set_cell(&mut self, r: usize, c: usize, p: Piece) {
-        self.cells[r * N + c] = Some(p);
+        self.clear_cell(r, c);
+        self.toggle(r * STRIDE + c, p);
     }
 
     pub fn get_cell(&self, r: usize, c: usize) -> Option<Piece> {
-        self.cells[r * N + c]
+        let sq = r * STRIDE + c;
+        if !self.occupied.contains(sq) {
+            return None;
+        }
+        let piece_type = PieceType::ALL[self.by_type.iter().position(|bb| bb.contains(sq))?];
+        let move_left = self.by_move_left.iter().position(|bb| bb.contains(sq))?;
+        Some(Piece::with_moves(piece_type, move_left))
     }
 
     pub fn clear_cell(&mut self, r: usize, c: usize) {
-        self.cells[r * N + c] = None;
+        if let Some(p) = self.get_cell(r, c) {
+            self.toggle(r * STRIDE + c, p);
+        }
+    }
+
+    /// Adds `p` to the empty square `sq`, or removes it if it is already there.
+    fn toggle(&mut self, sq: usize, p: Piece) {
+        let b = Bitboard::square(sq);
+        self.occupied ^= b;
+        self.by_type[p.piece_type as usize] ^= b;
+        self.by_move_left[p.move_left()] ^= b;
+        self.key ^= zobrist::key(p, sq);
+        self.mirror_key ^= zobrist::key(p, self.mirror(sq));
     }
 
     pub fn move_piece(&mut self, fr: usize, fc: usize, tr: usize, tc: usize) {
@@ -39,38 +136,91 @@ impl Board {
         }
     }
 
-    pub fn pieces(&self) -> impl Iterator<Item = (usize, usize, &Piece)> {
-        self.cells
-            .iter()
-            .enumerate()
-            .filter_map(|(i, p)| p.as_ref().map(|p| (i / N, i % N, p)))
+    pub fn pieces(&self) -> impl Iterator<Item = (usize, usize, Piece)> + '_ {
+        squares(self.occupied()).map(|sq| {
+            let (r, c) = (sq / STRIDE, sq % STRIDE);
+            (r, c, self.get_cell(r, c).unwrap())
+        })
+    }
+
+    /// Zobrist key of the position, maintained incrementally as pieces are set and cleared.
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Key shared by the position and its mirror image. Only a goal square tells the two sides
+    /// of the board apart; without one, a mirrored position has mirrored solutions.
+    pub fn canonical_key(&self) -> u64 {
+        self.key.min(self.mirror_key)
+    }
+
+    /// The position reflected left to right.
+    pub fn mirrored(&self) -> Board {
+        let mut board = Board::with_shape(self.shape);
+        for (r, c, p) in self.pieces() {
+            board.set_cell(r, self.shape.cols - 1 - c, p);
+        }
+        board
+    }
+
+    /// Every occupied square.
+    pub fn occupied(&self) -> Bitboard {
+        self.occupied
+    }
+
+    /// Squares holding a piece of `piece_type`.
+    pub fn of_type(&self, piece_type: PieceType) -> Bitboard {
+        self.by_type[piece_type as usize]
+    }
+
+    /// Squares holding a piece that can still capture.
+    pub fn movable(&self) -> Bitboard {
+        self.occupied() & !self.by_move_left[0]
     }
 
     pub fn count_pieces(&self) -> usize {
-        self.pieces().count()
+        self.occupied().count()
     }
 
+    /// Total captures the pieces may still make. A piece without a limit counts as every
+    /// capture left on the board.
     pub fn sum_move_left(&self) -> usize {
-        self.pieces().map(|(_, _, p)| p.move_left()).sum()
+        let unlimited = self.by_move_left[Piece::UNLIMITED].count();
+        let limited: usize = self.by_move_left[..Piece::UNLIMITED]
+            .iter()
+            .enumerate()
+            .map(|(n, bb)| n * bb.count())
+            .sum();
+        limited + unlimited * self.count_pieces().saturating_sub(1)
+    }
+
+    /// Gives every piece the full capture allowance of `rules`, as if none had moved yet.
+    pub fn reset_moves(&mut self, rules: &Rules) {
+        for (r, c, p) in self.pieces().collect::<Vec<_>>() {
+            self.set_cell(r, c, rules.piece(p.piece_type));
+        }
     }
 
     pub fn has_king(&self) -> bool {
-        self.pieces().any(|(_, _, p)| p.is_king())
+        !self.of_type(PieceType::King).is_empty()
     }
 
     pub fn single_is_king(&self) -> bool {
-        let mut it = self.pieces();
-        let Some((_, _, p)) = it.next() else {
-            return false;
-        };
-        it.next().is_none() && p.is_king()
+        let occupied = self.occupied();
+        occupied.count() == 1 && occupied == self.of_type(PieceType::King)
+    }
+
+    /// The square `sq` is reflected onto by [`Board::mirrored`].
+    fn mirror(&self, sq: usize) -> usize {
+        sq - sq % STRIDE + self.shape.cols - 1 - sq % STRIDE
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::board::Board;
+    use crate::board::{Board, Shape};
     use crate::piece::{Piece, PieceType};
+    use crate::rules::Rules;
 
     #[test]
     fn count_pieces() {
@@ -102,8 +252,7 @@ mod test {
         board.set_cell(4, 7, Piece::new(PieceType::Pawn));
 
         assert_eq!(3, board.count_pieces());
-        let pieces: Vec<(usize, usize, Piece)> =
-            board.pieces().map(|(r, c, p)| (r, c, *p)).collect();
+        let pieces: Vec<(usize, usize, Piece)> = board.pieces().collect();
         assert_eq!(
             vec![
                 (0, 4, Piece::new(PieceType::Bishop)),
@@ -113,4 +262,125 @@ mod test {
             pieces
         );
     }
+
+    #[test]
+    fn set_cell_replaces_existing_piece() {
+        let mut board = Board::new();
+        board.set_cell(5, 5, Piece::new(PieceType::Rook));
+        board.move_piece(5, 5, 5, 6);
+        board.set_cell(5, 6, Piece::new(PieceType::Bishop));
+
+        assert_eq!(1, board.count_pieces());
+        assert_eq!(Some(Piece::new(PieceType::Bishop)), board.get_cell(5, 6));
+        assert!(!board.has_king());
+    }
+
+    #[test]
+    fn sum_move_left_and_single_king() {
+        let mut board = Board::new();
+        board.set_cell(1, 1, Piece::new(PieceType::King));
+        assert!(board.single_is_king());
+
+        board.set_cell(1, 2, Piece::new(PieceType::Pawn));
+        board.move_piece(1, 1, 1, 2);
+        assert!(board.single_is_king());
+        assert_eq!(1, board.sum_move_left());
+
+        board.set_cell(6, 6, Piece::new(PieceType::Knight));
+        assert!(!board.single_is_king());
+        assert_eq!(3, board.sum_move_left());
+
+        // A piece without a limit could make every remaining capture.
+        board.set_cell(0, 0, Piece::with_moves(PieceType::Rook, Piece::UNLIMITED));
+        assert_eq!(5, board.sum_move_left());
+    }
+
+    #[test]
+    fn reset_moves_follows_the_capture_limit() {
+        let mut board = Board::new();
+        board.set_cell(1, 1, Piece::new(PieceType::Bishop));
+        board.set_cell(2, 2, Piece::new(PieceType::Pawn));
+        board.move_piece(1, 1, 2, 2);
+
+        let rules = Rules {
+            capture_limit: Some(3),
+            ..Rules::default()
+        };
+        board.reset_moves(&rules);
+        assert_eq!(Some(rules.piece(PieceType::Bishop)), board.get_cell(2, 2));
+        assert_eq!(3, board.sum_move_left());
+    }
+
+    #[test]
+    fn zobrist_key_follows_position() {
+        let mut a = Board::new();
+        a.set_cell(0, 4, Piece::new(PieceType::Bishop));
+        a.set_cell(2, 6, Piece::new(PieceType::Rook));
+
+        let mut b = Board::new();
+        b.set_cell(2, 6, Piece::new(PieceType::Rook));
+        b.set_cell(0, 4, Piece::new(PieceType::Bishop));
+        assert_eq!(a.key(), b.key());
+
+        // Capturing changes the key, and undoing the capture restores it.
+        let before = a.key();
+        a.move_piece(0, 4, 2, 6);
+        assert_ne!(before, a.key());
+        a.set_cell(0, 4, Piece::new(PieceType::Bishop));
+        a.set_cell(2, 6, Piece::new(PieceType::Rook));
+        assert_eq!(before, a.key());
+
+        // The remaining-move count is part of the key.
+        b.move_piece(0, 4, 1, 5);
+        b.move_piece(1, 5, 0, 4);
+        assert_ne!(a.key(), b.key());
+
+        b.clear_cell(0, 4);
+        b.clear_cell(2, 6);
+        assert_eq!(0, b.key());
+    }
+
+    #[test]
+    fn mirror_images_share_a_canonical_key() {
+        let mut a = Board::new();
+        a.set_cell(0, 1, Piece::new(PieceType::Bishop));
+        a.set_cell(3, 5, Piece::new(PieceType::Pawn));
+        a.move_piece(0, 1, 3, 4);
+
+        let b = a.mirrored();
+        assert_eq!(Some(Piece::new(PieceType::Pawn)), b.get_cell(3, 2));
+        assert_eq!(1, b.get_cell(3, 3).unwrap().move_left());
+        assert_ne!(a.key(), b.key());
+        assert_eq!(a.canonical_key(), b.canonical_key());
+        assert!(a == b.mirrored());
+
+        a.clear_cell(3, 5);
+        assert_ne!(a.canonical_key(), b.canonical_key());
+    }
+
+    #[test]
+    fn resized_keeps_the_pieces_that_fit() {
+        assert_eq!(None, Shape::new(2, 8));
+        assert_eq!(None, Shape::new(8, 17));
+        let shape = Shape::new(4, 6).unwrap();
+
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::new(PieceType::Rook));
+        board.set_cell(3, 5, Piece::with_moves(PieceType::Knight, 1));
+        board.set_cell(4, 0, Piece::new(PieceType::Queen));
+        board.set_cell(0, 6, Piece::new(PieceType::Queen));
+
+        let small = board.resized(shape);
+        assert_eq!(shape, small.shape());
+        assert_eq!(2, small.count_pieces());
+        assert_eq!(
+            Some(Piece::with_moves(PieceType::Knight, 1)),
+            small.get_cell(3, 5)
+        );
+
+        // Mirroring follows the board's own width.
+        let mirrored = small.mirrored();
+        assert_eq!(Some(Piece::new(PieceType::Rook)), mirrored.get_cell(0, 5));
+        assert_eq!(small.canonical_key(), mirrored.canonical_key());
+    }
 }
@@ -0,0 +1,275 @@
+use crate::bitboard::{Bitboard, attacks};
+use crate::board::{Board, STRIDE, Shape};
+use crate::piece::PieceType;
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::solver::difficulty::rate_with;
+use crate::solver::{SolutionCount, SolverConfig, count_solutions_with};
+
+/// Boards built before [`generate`] settles for fewer pieces than asked for.
+const ATTEMPTS: usize = 100;
+
+/// Filters a generated puzzle has to pass, and the board it is set on.
+#[derive(Clone, Debug)]
+pub struct GenerateConfig {
+    pub shape: Shape,
+    /// Keep only puzzles with exactly one solution.
+    pub unique: bool,
+    /// Keep only puzzles rated at least this level, see [`crate::solver::difficulty`].
+    pub min_level: Option<u8>,
+    /// Positions the solver may visit while checking one puzzle against the filters. A puzzle
+    /// it cannot settle in time is dropped.
+    pub max_nodes: Option<u64>,
+    /// Boards to build before giving up.
+    pub attempts: usize,
+}
+
+impl Default for GenerateConfig {
+    fn default() -> Self {
+        Self {
+            shape: Shape::STANDARD,
+            unique: false,
+            min_level: None,
+            max_nodes: Some(1_000_000),
+            attempts: ATTEMPTS,
+        }
+    }
+}
+
+/// A solvable 8x8 puzzle with `n_pieces` pieces drawn from `piece_mix` under `rules`. The same
+/// seed always gives the same puzzle. Listing a type more than once in `piece_mix` makes it
+/// more common.
+///
+/// The board is built backwards from the last piece: every step undoes a capture, putting the
+/// captured piece back on the square its capturer vacates and giving the capturer its capture
+/// back. Each step is checked against the position it leads to, so the captures replayed
+/// forwards always solve the puzzle.
+///
+/// A King in the mix is the last piece whenever the rules do not let it be captured. Fewer
+/// pieces come back only when no board of `n_pieces` could be built.
+///
+/// # Panics
+///
+/// If `piece_mix` is empty.
+pub fn generate(n_pieces: usize, piece_mix: &[PieceType], rules: &Rules, seed: u64) -> Board {
+    let mut rng = Rng::new(seed);
+    let mut best = Board::new();
+    for _ in 0..ATTEMPTS {
+        let board = build(Shape::STANDARD, n_pieces, piece_mix, rules, &mut rng);
+        if board.count_pieces() > best.count_pieces() {
+            best = board;
+        }
+        if best.count_pieces() == n_pieces {
+            break;
+        }
+    }
+    best
+}
+
+/// Like [`generate`], on the board and with the filters of `config`. Returns `None` if no
+/// puzzle passed within `config.attempts` boards.
+pub fn generate_with(
+    n_pieces: usize,
+    piece_mix: &[PieceType],
+    rules: &Rules,
+    seed: u64,
+    config: &GenerateConfig,
+) -> Option<Board> {
+    let mut rng = Rng::new(seed);
+    let solver = SolverConfig {
+        max_nodes: config.max_nodes,
+        rules: *rules,
+        ..SolverConfig::default()
+    };
+    (0..config.attempts)
+        .map(|_| build(config.shape, n_pieces, piece_mix, rules, &mut rng))
+        .find(|board| board.count_pieces() == n_pieces && passes(board, config, &solver))
+}
+
+fn passes(board: &Board, config: &GenerateConfig, solver: &SolverConfig) -> bool {
+    if let Some(min) = config.min_level
+        && rate_with(board, solver).is_none_or(|difficulty| difficulty.level < min)
+    {
+        return false;
+    }
+    !config.unique || count_solutions_with(board, solver) == Some(SolutionCount::Unique)
+}
+
+/// Undoes captures from a single last piece until `n_pieces` stand on the board or no capture
+/// can be undone.
+fn build(
+    shape: Shape,
+    n_pieces: usize,
+    piece_mix: &[PieceType],
+    rules: &Rules,
+    rng: &mut Rng,
+) -> Board {
+    assert!(!piece_mix.is_empty(), "the piece mix is empty");
+    let mut board = Board::with_shape(shape);
+    let pawns = rules.pawn_direction;
+
+    // A King that may not be captured can only ever be the last piece.
+    let king_last = rules.king_must_survive || !rules.kings_capturable;
+    let last = match rules.goal.piece_type {
+        Some(piece_type) => piece_type,
+        None if king_last && piece_mix.contains(&PieceType::King) => PieceType::King,
+        None => *rng.pick(piece_mix),
+    };
+    let captured: Vec<PieceType> = piece_mix
+        .iter()
+        .copied()
+        .filter(|&t| t != PieceType::King || !king_last)
+        .collect();
+
+    let (r, c) = match rules.goal.square {
+        Some((r, c)) if shape.contains(r, c) => (r, c),
+        _ => (rng.below(shape.rows), rng.below(shape.cols)),
+    };
+    board.set_cell(r, c, rules.piece(last));
+    if captured.is_empty() {
+        return board;
+    }
+
+    // Pieces keep the captures they have not been given yet, so a piece can only undo a
+    // capture while it still has one to spare.
+    let mut undos = Vec::new();
+    while board.count_pieces() < n_pieces {
+        undos.clear();
+        let occupied = board.occupied();
+        for (tr, tc, p) in board.pieces().filter(|(_, _, p)| p.move_left() > 0) {
+            let to = tr * STRIDE + tc;
+            for fr in 0..shape.rows {
+                for fc in 0..shape.cols {
+                    let from = fr * STRIDE + fc;
+                    let before = occupied | Bitboard::square(from);
+                    if !occupied.contains(from)
+                        && attacks(shape, p.piece_type, from, before, pawns).contains(to)
+                    {
+                        undos.push(((fr, fc), (tr, tc), p));
+                    }
+                }
+            }
+        }
+        if undos.is_empty() {
+            break;
+        }
+
+        let &((fr, fc), (tr, tc), mut p) = rng.pick(&undos);
+        p.use_move();
+        board.set_cell(fr, fc, p);
+        board.set_cell(tr, tc, rules.piece(*rng.pick(&captured)));
+    }
+
+    board.reset_moves(rules);
+    board
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::{Goal, PawnDirection};
+    use crate::solver::{SolveOutcome, difficulty::rate, solve_with, validate::validate};
+
+    const MIX: &[PieceType] = &[
+        PieceType::King,
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Pawn,
+    ];
+
+    fn solves(board: &Board, rules: &Rules) -> bool {
+        let config = SolverConfig {
+            rules: *rules,
+            ..SolverConfig::default()
+        };
+        match solve_with(board, &config) {
+            SolveOutcome::Solved(steps) => validate(board, &steps, rules).is_ok(),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn generated_puzzles_are_solvable() {
+        let rules = Rules::default();
+        for seed in 0..20 {
+            let board = generate(8, MIX, &rules, seed);
+            assert_eq!(8, board.count_pieces());
+            assert_eq!(1, board.of_type(PieceType::King).count());
+            assert!(
+                board
+                    .pieces()
+                    .all(|(_, _, p)| p == rules.piece(p.piece_type))
+            );
+            assert!(solves(&board, &rules), "seed {seed}");
+        }
+        assert!(generate(8, MIX, &rules, 7) == generate(8, MIX, &rules, 7));
+    }
+
+    #[test]
+    fn variant_rules_and_shapes() {
+        let rules = Rules {
+            capture_limit: Some(1),
+            kings_capturable: true,
+            king_must_survive: false,
+            pawn_direction: PawnDirection::Down,
+            goal: Goal {
+                piece_type: Some(PieceType::Nightrider),
+                square: Some((2, 3)),
+            },
+        };
+        let mix = &[
+            PieceType::King,
+            PieceType::Pawn,
+            PieceType::Nightrider,
+            PieceType::Grasshopper,
+        ];
+        let config = GenerateConfig {
+            shape: Shape::new(5, 7).unwrap(),
+            ..GenerateConfig::default()
+        };
+        for seed in 0..10 {
+            let board = generate_with(6, mix, &rules, seed, &config).unwrap();
+            assert_eq!(Shape::new(5, 7), Some(board.shape()));
+            assert_eq!(6, board.count_pieces());
+            assert!(solves(&board, &rules), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn filters_use_the_solver() {
+        let rules = Rules::default();
+        let solver = SolverConfig::default();
+        let config = GenerateConfig {
+            unique: true,
+            attempts: 500,
+            ..GenerateConfig::default()
+        };
+        let board = generate_with(6, MIX, &rules, 3, &config).unwrap();
+        assert_eq!(
+            Some(SolutionCount::Unique),
+            count_solutions_with(&board, &solver)
+        );
+
+        let config = GenerateConfig {
+            min_level: Some(6),
+            ..GenerateConfig::default()
+        };
+        let board = generate_with(7, MIX, &rules, 3, &config).unwrap();
+        assert!(rate(&board).unwrap().level >= 6);
+    }
+
+    #[test]
+    fn nothing_left_to_capture() {
+        // A King that has to survive is never captured, so it stands alone.
+        let rules = Rules::default();
+        let board = generate(5, &[PieceType::King], &rules, 1);
+        assert!(board.single_is_king());
+        let config = GenerateConfig {
+            attempts: 5,
+            ..GenerateConfig::default()
+        };
+        assert!(generate_with(5, &[PieceType::King], &rules, 1, &config).is_none());
+    }
+}
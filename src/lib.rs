@@ -0,0 +1,10 @@
+pub mod bitboard;
+pub mod board;
+pub mod generator;
+pub mod piece;
+mod rng;
+pub mod rules;
+pub mod solver;
+pub mod step;
+pub mod ui;
+mod zobrist;
@@ -1,20 +1,37 @@
-use dioxus::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-mod board;
-mod piece;
-mod solver;
-mod step;
-mod ui;
+use dioxus::prelude::*;
+use web_time::{SystemTime, UNIX_EPOCH};
 
-use board::Board;
-use piece::{Piece, PieceType};
-use solver::solo_chess_solver;
-use ui::chessboard::Chessboard;
-use ui::piece_selection::PieceSelectionBoard;
-use ui::solution::Solution;
-use ui::step_arrows::StepArrows;
+use solo_chess_solver::board::{Board, Shape};
+use solo_chess_solver::generator::{GenerateConfig, generate_with};
+use solo_chess_solver::piece::PieceType;
+use solo_chess_solver::rules::{PawnDirection, Rules};
+use solo_chess_solver::solver::best_effort::{BestEffort, BestEffortTask};
+use solo_chess_solver::solver::strategy::{Solver, Strategy};
+use solo_chess_solver::solver::tablebase::Tablebase;
+use solo_chess_solver::solver::{
+    InvalidPosition, Progress, SolveOutcome, SolveStats, SolverConfig, explain_unsolvable,
+};
+use solo_chess_solver::ui::background::yield_now;
+use solo_chess_solver::ui::board_size::BoardSize;
+use solo_chess_solver::ui::chessboard::Chessboard;
+use solo_chess_solver::ui::goal::GoalSettings;
+use solo_chess_solver::ui::piece_selection::PieceSelectionBoard;
+use solo_chess_solver::ui::rules::RulesSettings;
+use solo_chess_solver::ui::solution::Solution;
+use solo_chess_solver::ui::stats::SearchStats;
+use solo_chess_solver::ui::step_arrows::StepArrows;
+use solo_chess_solver::ui::strategy::StrategySettings;
 
 const STYLE: Asset = asset!("/assets/style.css");
+/// Tablebase for the default rules on the standard board, written by
+/// `examples/build_tablebase.rs`.
+const TABLEBASE: Asset = asset!("/assets/tablebase.bin");
+
+/// Nodes searched between handing control back to the browser.
+const SOLVE_SLICE_NODES: u64 = 20_000;
 
 fn main() {
     dioxus::launch(App);
@@ -27,12 +44,20 @@ fn App() -> Element {
     let mut board_state = use_signal(|| board);
     let mut selected_square = use_signal(|| Option::<(usize, usize)>::None);
     let selected_piece = use_signal(|| Option::<usize>::None);
+    let mut rules = use_signal(Rules::default);
+    let mut picking_goal = use_signal(|| false);
+    let mut strategy = use_signal(Strategy::default);
 
     let on_square_click = move |(r, c): (usize, usize)| {
+        if picking_goal() {
+            rules.with_mut(|rules| rules.goal.square = Some((r, c)));
+            picking_goal.set(false);
+            return;
+        }
         selected_square.set(Some((r, c)));
         if let Some(p) = selected_piece() {
             board_state.with_mut(|b| {
-                b.set_cell(r, c, Piece::new(PieceType::ALL[p]));
+                b.set_cell(r, c, rules().piece(PieceType::ALL[p]));
             });
         }
     };
@@ -43,9 +68,109 @@ fn App() -> Element {
         });
     };
 
-    let mut solver_board = board_state();
-    let steps = solo_chess_solver(&mut solver_board);
-    let selected_step = use_signal(|| Option::<usize>::None);
+    let mut outcome = use_signal(|| SolveOutcome::InvalidPosition(InvalidPosition::EmptyBoard));
+    let mut progress = use_signal(|| Option::<Progress>::None);
+    let mut stats = use_signal(|| Option::<SolveStats>::None);
+    let mut best_effort = use_signal(|| Option::<BestEffort>::None);
+    // Shows only the next capture of the solution, the one `next_hint` gives, until the player
+    // asks for the rest. Every new position starts hidden again.
+    let mut hint_only = use_signal(|| true);
+    let mut cancel = use_signal(|| Arc::new(AtomicBool::new(false)));
+    let mut selected_step = use_signal(|| Option::<usize>::None);
+    // The shipped table, and one for the current board shape and rules when it does not cover
+    // them, so what a search learns carries over to the next one. A table for a shape or rules
+    // left behind is dropped. Searches only peek, so swapping tables does not restart them.
+    let mut shipped_tablebase = use_signal(|| Option::<Arc<Tablebase>>::None);
+    let mut tablebase = use_signal(|| Option::<Arc<Tablebase>>::None);
+
+    use_future(move || async move {
+        let Ok(bytes) = dioxus::asset_resolver::read_asset_bytes(&TABLEBASE).await else {
+            return;
+        };
+        if let Ok(tablebase) = Tablebase::from_bytes(&bytes) {
+            shipped_tablebase.set(Some(Arc::new(tablebase)));
+        }
+    });
+
+    // Restarts, dropping the previous search, whenever the board, rules or strategy change. The
+    // last outcome stays on screen until the new search settles.
+    use_resource(move || async move {
+        let board = board_state();
+        let rules = rules();
+        hint_only.set(true);
+        let covering = [shipped_tablebase.peek().clone(), tablebase.peek().clone()]
+            .into_iter()
+            .flatten()
+            .find(|tablebase| tablebase.covers(&board, &rules));
+        let covering = covering.unwrap_or_else(|| {
+            let new = Arc::new(Tablebase::new(board.shape(), &rules));
+            tablebase.set(Some(new.clone()));
+            new
+        });
+        let config = SolverConfig {
+            rules,
+            tablebase: Some(covering),
+            ..SolverConfig::default()
+        };
+        cancel.set(config.cancel.clone());
+
+        let mut task = strategy().start(&board, &config);
+        let result = loop {
+            if let Some(result) = task.run(SOLVE_SLICE_NODES) {
+                break result;
+            }
+            progress.set(Some(task.progress()));
+            yield_now().await;
+        };
+        best_effort.set(None);
+        let unsolvable = result == SolveOutcome::Unsolvable;
+        outcome.set(result);
+        stats.set(Some(task.stats()));
+
+        // Show how close the position comes instead. This can take as long as the search
+        // itself, so it keeps reporting progress and can be cancelled too.
+        if unsolvable {
+            let mut task = BestEffortTask::new(&board, &config);
+            let best = loop {
+                if let Some(best) = task.run(SOLVE_SLICE_NODES) {
+                    break best;
+                }
+                progress.set(Some(task.progress()));
+                yield_now().await;
+            };
+            best_effort.set(Some(best));
+        }
+        progress.set(None);
+    });
+
+    let on_cancel = move |_| cancel.read().store(true, Ordering::Relaxed);
+
+    let on_rules_change = move |new: Rules| {
+        if new.capture_limit != rules().capture_limit {
+            board_state.with_mut(|b| b.reset_moves(&new));
+        }
+        rules.set(new);
+    };
+
+    let on_shape_change = move |shape: Shape| {
+        board_state.with_mut(|b| *b = b.resized(shape));
+        rules.with_mut(|rules| {
+            if rules
+                .goal
+                .square
+                .is_some_and(|(r, c)| !shape.contains(r, c))
+            {
+                rules.goal.square = None;
+            }
+        });
+        selected_step.set(None);
+    };
+
+    let explanation = if progress().is_none() && outcome() == SolveOutcome::Unsolvable {
+        explain_unsolvable(&board_state(), &rules())
+    } else {
+        None
+    };
 
     rsx! {
         document::Meta { name: "viewport", content: "width=device-width, initial-scale=1" }
@@ -71,43 +196,105 @@ fn App() -> Element {
                             class: "preset-button",
                             r#type: "button",
                             onclick: {
-                                let board_state = board_state;
-                                let selected_step = selected_step;
                                 let pieces = setup.pieces;
-                                move |_| apply_preset(board_state, selected_step, pieces)
+                                move |_| apply_preset(board_state, selected_step, &rules(), pieces)
                             },
                             "{setup.name}"
                         }
                     }
+                    button {
+                        class: "preset-button",
+                        r#type: "button",
+                        onclick: move |_| random_puzzle(board_state, selected_step, &rules()),
+                        "Random puzzle"
+                    }
                 }
             }
             div {
                 class: "board-stack",
                 Chessboard {
                     board: board_state,
+                    goal_square: rules().goal.square,
                     on_square_click,
                     on_square_right_click,
                 }
                 StepArrows {
-                    steps: steps.clone(),
+                    shape: board_state.read().shape(),
+                    steps: match best_effort() {
+                        Some(best) => best.steps,
+                        None if hint_only() => outcome.read().steps().iter().take(1).copied().collect(),
+                        None => outcome.read().steps().to_vec(),
+                    },
                     selected_step,
                 }
             }
+            if let Some(explanation) = explanation {
+                p {
+                    class: "unsolvable-reason",
+                    "Why: {explanation.describe(board_state.read().shape().rows)}."
+                }
+            }
             PieceSelectionBoard { selected: selected_piece }
-            Solution { steps, selected_step }
+            BoardSize { shape: board_state.read().shape(), on_change: on_shape_change }
+            RulesSettings { rules: rules(), on_change: on_rules_change }
+            StrategySettings { strategy: strategy(), on_change: move |new| strategy.set(new) }
+            GoalSettings {
+                goal: rules().goal,
+                rows: board_state.read().shape().rows,
+                picking: picking_goal,
+                on_change: move |goal| rules.with_mut(|rules| rules.goal = goal),
+            }
+            Solution {
+                outcome: outcome(),
+                best_effort: best_effort(),
+                rows: board_state.read().shape().rows,
+                progress: progress(),
+                selected_step,
+                hint_only,
+                on_cancel,
+            }
+            if let Some(stats) = stats() {
+                SearchStats { stats }
+            }
             section {
                 h2 { "Rules" }
-                p { "From chess.com: " }
+                p { "From chess.com, with the rule variant chosen above: " }
                 ul {
                     li { "Capture a piece with every move until just one remains." }
-                    li { "No piece may capture more than 2 times per puzzle (shown in black if cannot move)." }
-                    li { "If there is a King on the board, it must be the final piece." }
+                    li { {capture_limit_rule(rules().capture_limit)} }
+                    if rules().king_must_survive {
+                        li { "If there is a King on the board, it must be the final piece." }
+                    }
+                    if rules().kings_capturable {
+                        li { "Kings may be captured like any other piece." }
+                    } else {
+                        li { "Kings may not be captured." }
+                    }
+                    if rules().pawn_direction == PawnDirection::Up {
+                        li { "Pawns capture diagonally up the board, like White's pawns." }
+                    } else {
+                        li { "Pawns capture diagonally down the board, like Black's pawns." }
+                    }
                 }
             }
         }
     }
 }
 
+/// The capture limit as a line of the rules list.
+fn capture_limit_rule(capture_limit: Option<usize>) -> String {
+    match capture_limit {
+        Some(1) => {
+            "No piece may capture more than once per puzzle (shown in black if cannot move)."
+                .to_string()
+        }
+        Some(n) => format!(
+            "No piece may capture more than {n} times per puzzle (shown in black if cannot move)."
+        ),
+        None => "Pieces may capture any number of times.".to_string(),
+    }
+}
+
 struct ExampleSetup {
     name: &'static str,
     pieces: &'static [(usize, usize, PieceType)],
@@ -145,19 +332,51 @@ const EXAMPLE_SETUPS: &[ExampleSetup] = &[
     },
 ];
 
+/// Pieces drawn for a random puzzle. The King, when drawn, is always the last piece.
+const RANDOM_PUZZLE_MIX: &[PieceType] = &[
+    PieceType::King,
+    PieceType::Queen,
+    PieceType::Rook,
+    PieceType::Rook,
+    PieceType::Bishop,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Knight,
+    PieceType::Pawn,
+    PieceType::Pawn,
+];
+
+/// Replaces the board with a new solvable puzzle of the same shape.
+fn random_puzzle(
+    mut board_state: Signal<Board>,
+    mut selected_step: Signal<Option<usize>>,
+    rules: &Rules,
+) {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let config = GenerateConfig {
+        shape: board_state.read().shape(),
+        ..GenerateConfig::default()
+    };
+    let n_pieces = 5 + (seed % 6) as usize;
+    if let Some(board) = generate_with(n_pieces, RANDOM_PUZZLE_MIX, rules, seed, &config) {
+        board_state.set(board);
+        selected_step.set(None);
+    }
+}
+
 fn apply_preset(
     mut board_state: Signal<Board>,
     mut selected_step: Signal<Option<usize>>,
+    rules: &Rules,
     pieces: &[(usize, usize, PieceType)],
 ) {
+    // The examples are all 8x8 puzzles.
     board_state.with_mut(|b| {
-        for r in 0..8 {
-            for c in 0..8 {
-                b.clear_cell(r, c);
-            }
-        }
+        *b = Board::new();
         for &(r, c, piece_type) in pieces {
-            b.set_cell(r, c, Piece::new(piece_type));
+            b.set_cell(r, c, rules.piece(piece_type));
         }
     });
     selected_step.set(None);
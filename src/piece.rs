@@ -9,8 +9,67 @@ pub enum PieceType {
     Rook,
     Knight,
     Pawn,
+    /// Bishop + Knight.
+    Archbishop,
+    /// Rook + Knight.
+    Chancellor,
+    /// Queen + Knight.
+    Amazon,
+    /// Leaps 3 squares one way and 1 the other.
+    Camel,
+    /// Repeats Knight leaps in one direction until it hits a piece.
+    Nightrider,
+    /// Jumps along Queen lines over exactly one piece, landing just beyond it.
+    Grasshopper,
 }
 
+/// How a piece captures, seen from the side whose pawns capture toward row 0. Offsets are
+/// `(row, column)` steps.
+#[derive(Clone, Copy, Debug)]
+pub struct Movement {
+    /// Jumps straight to the offset, over anything in between.
+    pub leaps: &'static [(i32, i32)],
+    /// Repeats the step until the first piece, which is the one captured.
+    pub rides: &'static [(i32, i32)],
+    /// Repeats the step until the first piece, then captures on the square just beyond it.
+    pub hops: &'static [(i32, i32)],
+}
+
+const DIAGONALS: &[(i32, i32)] = &[(-1, 1), (-1, -1), (1, -1), (1, 1)];
+const ORTHOGONALS: &[(i32, i32)] = &[(0, 1), (0, -1), (1, 0), (-1, 0)];
+const ALL_LINES: &[(i32, i32)] = &[
+    (-1, 1),
+    (-1, -1),
+    (1, -1),
+    (1, 1),
+    (0, 1),
+    (0, -1),
+    (1, 0),
+    (-1, 0),
+];
+const KNIGHT_LEAPS: &[(i32, i32)] = &[
+    (2, 1),
+    (-2, 1),
+    (2, -1),
+    (-2, -1),
+    (1, 2),
+    (-1, 2),
+    (1, -2),
+    (-1, -2),
+];
+const CAMEL_LEAPS: &[(i32, i32)] = &[
+    (3, 1),
+    (-3, 1),
+    (3, -1),
+    (-3, -1),
+    (1, 3),
+    (-1, 3),
+    (1, -3),
+    (-1, -3),
+];
+// Remainder: every move must be a capture under solo-chess rule, so pawns only move diagonally.
+const PAWN_CAPTURES: &[(i32, i32)] = &[(-1, 1), (-1, -1)];
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct Piece {
     move_left: usize,
@@ -19,10 +78,21 @@ pub struct Piece {
 
 impl Piece {
     const DEFAULT_MOVE_LEFT: usize = 2;
+    /// The highest capture limit a piece can have, short of [`Piece::UNLIMITED`].
+    pub const MAX_MOVE_LEFT: usize = 3;
+    /// Remaining-move count of a piece that may capture any number of times. It never goes
+    /// down.
+    pub const UNLIMITED: usize = Self::MAX_MOVE_LEFT + 1;
 
     pub fn new(piece_type: PieceType) -> Self {
+        Self::with_moves(piece_type, Self::DEFAULT_MOVE_LEFT)
+    }
+
+    /// A piece that may make `move_left` more captures, up to [`Piece::MAX_MOVE_LEFT`], or
+    /// [`Piece::UNLIMITED`].
+    pub fn with_moves(piece_type: PieceType, move_left: usize) -> Self {
         Self {
-            move_left: Self::DEFAULT_MOVE_LEFT,
+            move_left: move_left.min(Self::UNLIMITED),
             piece_type,
         }
     }
@@ -36,13 +106,19 @@ impl Piece {
     }
 
     pub fn use_move(&mut self) {
-        self.move_left = self.move_left.saturating_sub(1);
+        if !self.is_unlimited() {
+            self.move_left = self.move_left.saturating_sub(1);
+        }
     }
 
     pub fn move_left(&self) -> usize {
         self.move_left
     }
 
+    pub fn is_unlimited(&self) -> bool {
+        self.move_left == Self::UNLIMITED
+    }
+
     pub fn is_king(&self) -> bool {
         self.piece_type == PieceType::King
     }
@@ -57,6 +133,12 @@ impl fmt::Display for PieceType {
             PieceType::Rook => "R",
             PieceType::Knight => "N",
             PieceType::Pawn => "",
+            PieceType::Archbishop => "A",
+            PieceType::Chancellor => "C",
+            PieceType::Amazon => "Z",
+            PieceType::Camel => "L",
+            PieceType::Nightrider => "H",
+            PieceType::Grasshopper => "G",
         };
         write!(f, "{}", display_name)
     }
@@ -69,15 +151,39 @@ impl fmt::Display for Piece {
 }
 
 impl PieceType {
-    pub const ALL: [PieceType; 6] = [
+    pub const ALL: [PieceType; 12] = [
         PieceType::King,
         PieceType::Queen,
         PieceType::Bishop,
         PieceType::Rook,
         PieceType::Knight,
         PieceType::Pawn,
+        PieceType::Archbishop,
+        PieceType::Chancellor,
+        PieceType::Amazon,
+        PieceType::Camel,
+        PieceType::Nightrider,
+        PieceType::Grasshopper,
     ];
 
+    pub fn movement(&self) -> Movement {
+        let (leaps, rides, hops): (&[_], &[_], &[_]) = match self {
+            PieceType::King => (ALL_LINES, &[], &[]),
+            PieceType::Queen => (&[], ALL_LINES, &[]),
+            PieceType::Bishop => (&[], DIAGONALS, &[]),
+            PieceType::Rook => (&[], ORTHOGONALS, &[]),
+            PieceType::Knight => (KNIGHT_LEAPS, &[], &[]),
+            PieceType::Pawn => (PAWN_CAPTURES, &[], &[]),
+            PieceType::Archbishop => (KNIGHT_LEAPS, DIAGONALS, &[]),
+            PieceType::Chancellor => (KNIGHT_LEAPS, ORTHOGONALS, &[]),
+            PieceType::Amazon => (KNIGHT_LEAPS, ALL_LINES, &[]),
+            PieceType::Camel => (CAMEL_LEAPS, &[], &[]),
+            PieceType::Nightrider => (&[], KNIGHT_LEAPS, &[]),
+            PieceType::Grasshopper => (&[], &[], ALL_LINES),
+        };
+        Movement { leaps, rides, hops }
+    }
+
     pub fn get_white_icon(&self) -> Asset {
         match self {
             PieceType::King => asset!("/assets/img/white/king.png"),
@@ -86,6 +192,12 @@ impl PieceType {
             PieceType::Rook => asset!("/assets/img/white/rook.png"),
             PieceType::Knight => asset!("/assets/img/white/knight.png"),
             PieceType::Pawn => asset!("/assets/img/white/pawn.png"),
+            PieceType::Archbishop => asset!("/assets/img/white/archbishop.svg"),
+            PieceType::Chancellor => asset!("/assets/img/white/chancellor.svg"),
+            PieceType::Amazon => asset!("/assets/img/white/amazon.svg"),
+            PieceType::Camel => asset!("/assets/img/white/camel.svg"),
+            PieceType::Nightrider => asset!("/assets/img/white/nightrider.svg"),
+            PieceType::Grasshopper => asset!("/assets/img/white/grasshopper.svg"),
         }
     }
 
@@ -97,6 +209,12 @@ impl PieceType {
             PieceType::Rook => asset!("/assets/img/black/rook.png"),
             PieceType::Knight => asset!("/assets/img/black/knight.png"),
             PieceType::Pawn => asset!("/assets/img/black/pawn.png"),
+            PieceType::Archbishop => asset!("/assets/img/black/archbishop.svg"),
+            PieceType::Chancellor => asset!("/assets/img/black/chancellor.svg"),
+            PieceType::Amazon => asset!("/assets/img/black/amazon.svg"),
+            PieceType::Camel => asset!("/assets/img/black/camel.svg"),
+            PieceType::Nightrider => asset!("/assets/img/black/nightrider.svg"),
+            PieceType::Grasshopper => asset!("/assets/img/black/grasshopper.svg"),
         }
     }
 }
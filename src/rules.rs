@@ -0,0 +1,156 @@
+use crate::bitboard::{Bitboard, bit, reach, squares};
+use crate::board::{Board, STRIDE};
+use crate::piece::{Piece, PieceType};
+
+/// Which way pawns capture. Row 0 is the top of the board as drawn.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub enum PawnDirection {
+    /// Toward row 0, like White's pawns.
+    #[default]
+    Up,
+    /// Away from row 0, like Black's pawns.
+    Down,
+}
+
+/// The rules a puzzle is played under. [`Rules::default`] matches chess.com's Solo Chess.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Rules {
+    /// How many captures each piece may make. `None` means no limit. Limits above
+    /// [`Piece::MAX_MOVE_LEFT`] are treated as that many.
+    pub capture_limit: Option<usize>,
+    /// If a King is on the board, it has to be the last piece left.
+    pub king_must_survive: bool,
+    /// Whether other pieces may capture a King.
+    pub kings_capturable: bool,
+    pub pawn_direction: PawnDirection,
+    /// What the last piece has to be, on top of the King rule.
+    pub goal: Goal,
+}
+
+/// A target for the last piece left, such as "finish with the Knight on e4". Each part is
+/// optional; the default asks for nothing.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+pub struct Goal {
+    pub piece_type: Option<PieceType>,
+    /// `(row, column)` the last piece has to stand on.
+    pub square: Option<(usize, usize)>,
+}
+
+impl Goal {
+    /// Whether a piece `p` left alone on `(r, c)` meets the goal.
+    pub fn is_met_by(&self, r: usize, c: usize, p: Piece) -> bool {
+        self.piece_type.is_none_or(|t| t == p.piece_type) && self.square.is_none_or(|s| s == (r, c))
+    }
+
+    /// Whether a position and its mirror image are solved alike, which a goal square rules out.
+    pub fn is_mirror_symmetric(&self) -> bool {
+        self.square.is_none()
+    }
+
+    /// Squares of the pieces on `board` that could still end up meeting the goal, judged from
+    /// where each could possibly go with the moves it has left.
+    pub(crate) fn candidates(&self, board: &Board, rules: &Rules) -> Bitboard {
+        let of_type = match self.piece_type {
+            Some(piece_type) => board.of_type(piece_type),
+            None => board.occupied(),
+        };
+        let Some((r, c)) = self.square else {
+            return of_type;
+        };
+        let target = bit(r, c);
+        squares(of_type)
+            .filter(|&sq| {
+                let p = board.get_cell(sq / STRIDE, sq % STRIDE).unwrap();
+                let reached = reach(
+                    board.shape(),
+                    p.piece_type,
+                    sq,
+                    p.move_left(),
+                    rules.pawn_direction,
+                );
+                !((reached | Bitboard::square(sq)) & target).is_empty()
+            })
+            .collect()
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            capture_limit: Some(2),
+            king_must_survive: true,
+            kings_capturable: false,
+            pawn_direction: PawnDirection::Up,
+            goal: Goal::default(),
+        }
+    }
+}
+
+impl Rules {
+    /// The capture limits offered in the UI.
+    pub const CAPTURE_LIMITS: [Option<usize>; 4] = [Some(1), Some(2), Some(3), None];
+
+    /// A piece that has not captured yet.
+    pub fn piece(&self, piece_type: PieceType) -> Piece {
+        Piece::with_moves(piece_type, self.fresh_moves())
+    }
+
+    /// The remaining-move count of a piece that has not captured yet.
+    pub fn fresh_moves(&self) -> usize {
+        self.capture_limit
+            .map_or(Piece::UNLIMITED, |n| n.min(Piece::MAX_MOVE_LEFT))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fresh_pieces_follow_the_capture_limit() {
+        let rules = Rules::default();
+        assert_eq!(Piece::new(PieceType::Rook), rules.piece(PieceType::Rook));
+
+        let rules = Rules {
+            capture_limit: Some(7),
+            ..Rules::default()
+        };
+        assert_eq!(
+            Piece::MAX_MOVE_LEFT,
+            rules.piece(PieceType::Rook).move_left()
+        );
+
+        let rules = Rules {
+            capture_limit: None,
+            ..Rules::default()
+        };
+        let mut rook = rules.piece(PieceType::Rook);
+        for _ in 0..10 {
+            rook.use_move();
+        }
+        assert!(rook.is_unlimited());
+    }
+
+    #[test]
+    fn goal_candidates() {
+        let rules = Rules::default();
+        let mut board = Board::new();
+        board.set_cell(7, 0, Piece::new(PieceType::Knight));
+        board.set_cell(0, 0, Piece::new(PieceType::Knight));
+        board.set_cell(4, 4, Piece::new(PieceType::Rook));
+
+        let goal = Goal::default();
+        assert_eq!(board.occupied(), goal.candidates(&board, &rules));
+        assert!(goal.is_met_by(4, 4, Piece::new(PieceType::Rook)));
+
+        // e3 is two Knight moves from a1, but out of reach from a8.
+        let goal = Goal {
+            piece_type: Some(PieceType::Knight),
+            square: Some((5, 4)),
+        };
+        assert_eq!(bit(7, 0), goal.candidates(&board, &rules));
+        assert!(goal.is_met_by(5, 4, Piece::new(PieceType::Knight)));
+        assert!(!goal.is_met_by(5, 4, Piece::new(PieceType::Rook)));
+        assert!(!goal.is_met_by(4, 4, Piece::new(PieceType::Knight)));
+    }
+}
@@ -1,82 +1,719 @@
-use std::collections::HashSet;
-
-use crate::{board::Board, piece::PieceType, step::Step};
-
-// 0..2 are Pawn attack directions (Remainder: every move must be a capture under solo-chess rule);
-// 0..4 are Bishop move directions;
-// 4.. are Rook move directions.
-// Combine together they cover Queen and King directions.
-const SLIDER_MOVE: &[(i32, i32)] = &[
-    (-1, 1),
-    (-1, -1),
-    (1, -1),
-    (1, 1),
-    (0, 1),
-    (0, -1),
-    (1, 0),
-    (-1, 0),
-];
-
-const KNIGHT_MOVE: &[(i32, i32)] = &[
-    (2, 1),
-    (-2, 1),
-    (2, -1),
-    (-2, -1),
-    (1, 2),
-    (-1, 2),
-    (1, -2),
-    (-1, -2),
-];
-
-pub fn solo_chess_solver(board: &mut Board) -> Vec<Step> {
-    let king_required = board.has_king();
-    let mut steps = Vec::new();
-    let mut dead = HashSet::<Board>::new();
-
-    fn dfs(
-        board: &mut Board,
-        steps: &mut Vec<Step>,
-        dead: &mut HashSet<Board>,
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use web_time::Instant;
+
+use crate::{
+    bitboard::{attacks, squares},
+    board::{Board, SIZE, STRIDE},
+    piece::{Piece, PieceType},
+    rng::Rng,
+    rules::{Goal, Rules},
+    step::{Step, square_name},
+};
+
+pub mod best_effort;
+mod budget;
+pub mod difficulty;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+mod prune;
+pub mod sat;
+pub mod strategy;
+mod table;
+pub mod tablebase;
+pub mod validate;
+
+use budget::Budget;
+use table::{DeadSet, TranspositionTable};
+use tablebase::Tablebase;
+
+/// Knobs shared by every search entry point.
+#[derive(Clone, Debug)]
+pub struct SolverConfig {
+    /// Memory given to the table that memoizes searched positions, in MB.
+    pub table_mb: usize,
+    /// Give up after visiting this many positions.
+    pub max_nodes: Option<u64>,
+    /// Give up once this instant has passed.
+    pub deadline: Option<Instant>,
+    /// Set from elsewhere to stop a running search.
+    pub cancel: Arc<AtomicBool>,
+    pub rules: Rules,
+    /// Looked up, and filled in, whenever the search gets down to
+    /// [`tablebase::MAX_PIECES`] pieces. Ignored for boards and rules it was not built for.
+    pub tablebase: Option<Arc<Tablebase>>,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            table_mb: 16,
+            max_nodes: None,
+            deadline: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            rules: Rules::default(),
+            tablebase: None,
+        }
+    }
+}
+
+/// What the solver concluded about a position.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SolveOutcome {
+    Solved(Vec<Step>),
+    /// Exactly one piece is on the board, so there is nothing left to capture.
+    AlreadySolved,
+    Unsolvable,
+    InvalidPosition(InvalidPosition),
+    /// A budget in [`SolverConfig`] ran out, or the search was cancelled, before it reached a
+    /// conclusion.
+    Aborted {
+        nodes_searched: u64,
+    },
+}
+
+/// Why a position cannot be posed as a puzzle at all.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InvalidPosition {
+    EmptyBoard,
+    /// Kings can never be captured, so two of them can never be reduced to one piece.
+    MultipleKings,
+}
+
+impl SolveOutcome {
+    /// The capture sequence of a solved position, or nothing for any other outcome.
+    pub fn steps(&self) -> &[Step] {
+        match self {
+            SolveOutcome::Solved(steps) => steps,
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for InvalidPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            InvalidPosition::EmptyBoard => "the board is empty",
+            InvalidPosition::MultipleKings => "there is more than one King",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+pub fn solo_chess_solver(board: &mut Board) -> SolveOutcome {
+    solve_with(board, &SolverConfig::default())
+}
+
+pub fn solve_with(board: &Board, config: &SolverConfig) -> SolveOutcome {
+    solve_with_stats(board, config).0
+}
+
+/// One capture that keeps `board` solvable, or `None` if there is none.
+pub fn next_hint(board: &Board) -> Option<Step> {
+    next_hint_with(board, &SolverConfig::default())
+}
+
+pub fn next_hint_with(board: &Board, config: &SolverConfig) -> Option<Step> {
+    solve_with(board, config).steps().first().copied()
+}
+
+/// Like [`solve_with`], also returning how the search went.
+pub fn solve_with_stats(board: &Board, config: &SolverConfig) -> (SolveOutcome, SolveStats) {
+    let mut task = SolveTask::new(board, config);
+    loop {
+        if let Some(outcome) = task.run(u64::MAX) {
+            return (outcome, task.stats());
+        }
+    }
+}
+
+/// Snapshot of a running search.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Progress {
+    pub nodes: u64,
+    /// Number of captures made on the path being searched.
+    pub depth: usize,
+    /// Positions currently memoized as having no solution.
+    pub dead_positions: usize,
+}
+
+/// Counters collected while searching, for tuning the search itself.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SolveStats {
+    /// Positions whose captures were generated and tried.
+    pub expanded: u64,
+    /// Positions skipped because the memo table already knew them to be dead.
+    pub memo_hits: u64,
+    /// Positions cut because the remaining moves could not clear the board.
+    pub budget_prunes: u64,
+    /// Positions cut because a piece with no moves left could never be captured.
+    pub stranded_prunes: u64,
+    /// Positions cut because no piece could ever get next to the King.
+    pub king_prunes: u64,
+    /// Positions cut because the pieces split into groups that can never interact.
+    pub split_prunes: u64,
+    /// Positions cut because no piece that may finish could still meet the goal.
+    pub goal_prunes: u64,
+    /// Positions cut because the tablebase knew them to have no solution.
+    pub tablebase_prunes: u64,
+    pub max_depth: usize,
+    /// Time spent searching, not counting pauses between slices.
+    pub elapsed: Duration,
+}
+
+impl SolveStats {
+    /// Counts a position cut by `verdict`. Lost positions are not prunes and go uncounted.
+    fn count_prune(&mut self, verdict: Verdict) {
+        match verdict {
+            Verdict::Solved | Verdict::Lost => {}
+            Verdict::OutOfMoves => self.budget_prunes += 1,
+            Verdict::Stranded => self.stranded_prunes += 1,
+            Verdict::KingIsolated => self.king_prunes += 1,
+            Verdict::Split => self.split_prunes += 1,
+            Verdict::GoalOutOfReach => self.goal_prunes += 1,
+        }
+    }
+}
+
+/// A search for the first solution that can be run a slice at a time, so a UI can report
+/// progress and stay responsive in between.
+pub struct SolveTask {
+    solutions: Solutions,
+    outcome: Option<SolveOutcome>,
+}
+
+impl SolveTask {
+    pub fn new(board: &Board, config: &SolverConfig) -> Self {
+        Self {
+            solutions: solve_all_with(board, Some(1), config),
+            outcome: check_position(board, &config.rules),
+        }
+    }
+
+    /// Searches at most `slice` more nodes. Returns the outcome once it is known.
+    pub fn run(&mut self, slice: u64) -> Option<SolveOutcome> {
+        if self.outcome.is_none() {
+            self.outcome = match self.solutions.resume(slice) {
+                Resume::Found(steps) => Some(SolveOutcome::Solved(steps)),
+                Resume::Paused => None,
+                Resume::Finished if self.solutions.aborted() => Some(SolveOutcome::Aborted {
+                    nodes_searched: self.solutions.nodes_searched(),
+                }),
+                Resume::Finished => Some(SolveOutcome::Unsolvable),
+            };
+        }
+        self.outcome.clone()
+    }
+
+    pub fn progress(&self) -> Progress {
+        self.solutions.progress()
+    }
+
+    pub fn stats(&self) -> SolveStats {
+        self.solutions.stats()
+    }
+}
+
+/// Outcomes that follow from the position alone, without searching.
+fn check_position(board: &Board, rules: &Rules) -> Option<SolveOutcome> {
+    let p = board.count_pieces();
+    if p == 0 {
+        return Some(SolveOutcome::InvalidPosition(InvalidPosition::EmptyBoard));
+    }
+    if rules.king_must_survive && board.of_type(PieceType::King).count() > 1 {
+        return Some(SolveOutcome::InvalidPosition(
+            InvalidPosition::MultipleKings,
+        ));
+    }
+    if p == 1 {
+        return Some(match settle(board, rules, king_required(board, rules)) {
+            Some(Verdict::Solved) => SolveOutcome::AlreadySolved,
+            _ => SolveOutcome::Unsolvable,
+        });
+    }
+    None
+}
+
+/// Why a position has no solution, for the cases that can be shown without searching.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Explanation {
+    /// Fewer captures are left than it takes to clear the board.
+    OutOfMoves { captures_left: usize, pieces: usize },
+    /// A piece with no captures left that no other piece can ever reach.
+    Stranded {
+        square: (usize, usize),
+        piece_type: PieceType,
+    },
+    /// The King has to make the last capture, but nothing can ever get next to it.
+    KingIsolated { square: (usize, usize) },
+    /// Groups of pieces that can never capture one another.
+    Split { groups: Vec<Vec<(usize, usize)>> },
+    /// No piece that may be the last one left can meet the goal.
+    GoalOutOfReach,
+    /// A single piece is left, but it is not the one the rules or the goal ask for.
+    WrongLastPiece,
+}
+
+impl Explanation {
+    /// The explanation as a sentence fragment, naming squares for a board with `rows` ranks.
+    pub fn describe(&self, rows: usize) -> String {
+        let name = |&(r, c): &(usize, usize)| square_name(r, c, rows);
+        match self {
+            Explanation::OutOfMoves {
+                captures_left,
+                pieces,
+            } => format!(
+                "total remaining captures ({captures_left}) < pieces - 1 ({})",
+                pieces - 1
+            ),
+            Explanation::Stranded { square, piece_type } => format!(
+                "the {piece_type:?} on {} has no captures left and no other piece can ever reach it",
+                name(square)
+            ),
+            Explanation::KingIsolated { square } => format!(
+                "the King on {} can never be reached by any capture chain",
+                name(square)
+            ),
+            Explanation::Split { groups } => {
+                let groups: Vec<String> = groups
+                    .iter()
+                    .map(|group| group.iter().map(name).collect::<Vec<_>>().join(", "))
+                    .collect();
+                format!(
+                    "the pieces split into {} groups that can never interact: {}",
+                    groups.len(),
+                    groups.join("; ")
+                )
+            }
+            Explanation::GoalOutOfReach => {
+                "no piece that may be the last one left can ever meet the goal".to_string()
+            }
+            Explanation::WrongLastPiece => {
+                "the only piece on the board is not the one that has to be left".to_string()
+            }
+        }
+    }
+}
+
+/// Explains why `board` has no solution, when one of the checks the search prunes with
+/// already rules out the position itself. Returns `None` when that takes a search, and for
+/// positions that are solvable or not valid puzzles.
+pub fn explain_unsolvable(board: &Board, rules: &Rules) -> Option<Explanation> {
+    if matches!(
+        check_position(board, rules),
+        Some(SolveOutcome::InvalidPosition(_) | SolveOutcome::AlreadySolved)
+    ) {
+        return None;
+    }
+    let king_required = king_required(board, rules);
+    let cell = |sq: usize| (sq / STRIDE, sq % STRIDE);
+    match settle(board, rules, king_required)? {
+        Verdict::Solved => None,
+        Verdict::Lost => Some(Explanation::WrongLastPiece),
+        Verdict::OutOfMoves => Some(Explanation::OutOfMoves {
+            captures_left: board.sum_move_left(),
+            pieces: board.count_pieces(),
+        }),
+        Verdict::GoalOutOfReach => Some(Explanation::GoalOutOfReach),
+        Verdict::Stranded | Verdict::KingIsolated | Verdict::Split => {
+            Some(match prune::find_flaw(board, rules, king_required)? {
+                prune::Flaw::Stranded(sq) => {
+                    let square = cell(sq);
+                    Explanation::Stranded {
+                        square,
+                        piece_type: board.get_cell(square.0, square.1)?.piece_type,
+                    }
+                }
+                prune::Flaw::KingIsolated(sq) => Explanation::KingIsolated { square: cell(sq) },
+                split => Explanation::Split {
+                    groups: split
+                        .groups()
+                        .into_iter()
+                        .map(|group| group.into_iter().map(cell).collect())
+                        .collect(),
+                },
+            })
+        }
+    }
+}
+
+/// Lazily yields every distinct capture sequence that solves `board`, stopping after `cap`
+/// solutions when one is given.
+pub fn solve_all(board: &Board, cap: Option<usize>) -> Solutions {
+    solve_all_with(board, cap, &SolverConfig::default())
+}
+
+pub fn solve_all_with(board: &Board, cap: Option<usize>, config: &SolverConfig) -> Solutions {
+    let dead = DeadSet::Local(TranspositionTable::with_capacity_mb(
+        config.table_mb,
+        &config.rules,
+    ));
+    Solutions::new(
+        board,
+        king_required(board, &config.rules),
+        cap,
+        config,
+        dead,
+    )
+}
+
+/// Whether the last piece left has to be the King on `board`.
+fn king_required(board: &Board, rules: &Rules) -> bool {
+    rules.king_must_survive && board.has_king()
+}
+
+impl Solutions {
+    fn new(
+        board: &Board,
         king_required: bool,
-    ) -> bool {
-        let p = board.count_pieces();
-
-        if p <= 1 {
-            return if king_required {
-                board.single_is_king()
-            } else {
-                p == 1
+        cap: Option<usize>,
+        config: &SolverConfig,
+        dead: DeadSet,
+    ) -> Self {
+        Self {
+            board: *board,
+            rules: config.rules,
+            king_required,
+            dead,
+            tablebase: config
+                .tablebase
+                .clone()
+                .filter(|tablebase| tablebase.covers(board, &config.rules)),
+            budget: Budget::new(config),
+            stats: SolveStats::default(),
+            trail: Vec::new(),
+            stack: Vec::new(),
+            remaining: cap,
+            started: false,
+            discrepancy_limit: None,
+            cut: false,
+            shuffle: None,
+        }
+    }
+}
+
+/// Iterator returned by [`solve_all`].
+///
+/// The DFS runs on an explicit stack so it can pause after every solution. A board only goes
+/// into the dead set once its whole subtree has been searched without a solution, so boards
+/// left half-explored when the iterator stops are never marked dead.
+///
+/// When a budget runs out the iterator ends early; [`Solutions::aborted`] tells that apart from
+/// having found every solution.
+pub struct Solutions {
+    board: Board,
+    rules: Rules,
+    king_required: bool,
+    dead: DeadSet,
+    tablebase: Option<Arc<Tablebase>>,
+    budget: Budget,
+    stats: SolveStats,
+    trail: Vec<Undo>,
+    stack: Vec<Frame>,
+    remaining: Option<usize>,
+    started: bool,
+    /// How many times a line may pass over the first capture on offer, see
+    /// [`strategy::IterativeDeepening`]. `None` for no limit.
+    discrepancy_limit: Option<usize>,
+    /// Whether the limit kept any capture from being tried.
+    cut: bool,
+    /// Breaks ties between captures at random, see [`strategy::RandomRestarts`].
+    shuffle: Option<Rng>,
+}
+
+struct Frame {
+    moves: Vec<Step>,
+    next: usize,
+    solved: bool,
+    /// Times the line to this position passed over the first capture on offer.
+    discrepancies: usize,
+    /// Whether the discrepancy limit kept a capture below this position from being tried, so
+    /// finding no solution does not prove it dead.
+    cut: bool,
+}
+
+struct Undo {
+    step: Step,
+    from_piece: Piece,
+    to_piece: Piece,
+}
+
+enum Visit {
+    Solved,
+    Failed,
+    Open(Vec<Step>),
+}
+
+/// Where [`Solutions::resume`] stopped.
+pub enum Resume {
+    Found(Vec<Step>),
+    /// The slice of nodes ran out; call `resume` again to carry on.
+    Paused,
+    /// No more solutions, or a budget ran out.
+    Finished,
+}
+
+impl Solutions {
+    /// Whether the search stopped because a budget ran out.
+    pub fn aborted(&self) -> bool {
+        self.budget.exhausted()
+    }
+
+    pub fn nodes_searched(&self) -> u64 {
+        self.budget.nodes()
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            nodes: self.budget.nodes(),
+            depth: self.trail.len(),
+            dead_positions: self.dead.len(),
+        }
+    }
+
+    pub fn stats(&self) -> SolveStats {
+        self.stats
+    }
+
+    /// Searches until the next solution, the end of the search, or until `slice` more nodes
+    /// have been visited, whichever comes first.
+    pub fn resume(&mut self, slice: u64) -> Resume {
+        let start = Instant::now();
+        let resume = self.search(slice);
+        self.stats.elapsed += start.elapsed();
+        resume
+    }
+
+    fn search(&mut self, slice: u64) -> Resume {
+        let pause_at = self.budget.nodes().saturating_add(slice);
+
+        if self.remaining == Some(0) || self.aborted() {
+            return Resume::Finished;
+        }
+
+        if !self.started {
+            if slice == 0 {
+                return Resume::Paused;
+            }
+            self.started = true;
+            match self.visit() {
+                Some(Visit::Solved) => return self.found(),
+                Some(Visit::Open(moves)) => self.stack.push(Frame::new(moves)),
+                Some(Visit::Failed) | None => return Resume::Finished,
+            }
+        }
+
+        while let Some(frame) = self.stack.last_mut() {
+            let Some(&step) = frame.moves.get(frame.next) else {
+                let frame = self.stack.pop().unwrap();
+                if !frame.solved && !frame.cut {
+                    self.dead.insert(&self.board);
+                }
+                if let Some(parent) = self.stack.last_mut() {
+                    parent.solved |= frame.solved;
+                    parent.cut |= frame.cut;
+                    self.undo();
+                }
+                continue;
             };
+            let discrepancies = frame.discrepancies + (frame.next > 0) as usize;
+            if self
+                .discrepancy_limit
+                .is_some_and(|limit| discrepancies > limit)
+            {
+                frame.next = frame.moves.len();
+                frame.cut = true;
+                self.cut = true;
+                continue;
+            }
+            if self.budget.nodes() >= pause_at {
+                return Resume::Paused;
+            }
+            frame.next += 1;
+
+            self.apply(step);
+            match self.visit() {
+                Some(Visit::Solved) => {
+                    self.stack.last_mut().unwrap().solved = true;
+                    let solution = self.found();
+                    self.undo();
+                    return solution;
+                }
+                Some(Visit::Failed) => self.undo(),
+                Some(Visit::Open(moves)) => self.stack.push(Frame {
+                    discrepancies,
+                    ..Frame::new(moves)
+                }),
+                None => return Resume::Finished,
+            }
         }
 
-        if board.sum_move_left() < p - 1 {
-            return false;
+        Resume::Finished
+    }
+
+    fn visit(&mut self) -> Option<Visit> {
+        if !self.budget.tick() {
+            return None;
+        }
+        let board = &self.board;
+        match settle(board, &self.rules, self.king_required) {
+            Some(Verdict::Solved) => return Some(Visit::Solved),
+            Some(verdict) => {
+                self.stats.count_prune(verdict);
+                return Some(Visit::Failed);
+            }
+            None => {}
         }
 
-        if dead.contains(board) {
-            return false;
+        if self.dead.contains(board) {
+            self.stats.memo_hits += 1;
+            return Some(Visit::Failed);
         }
 
+        if let Some(tablebase) = &self.tablebase
+            && board.count_pieces() == tablebase::MAX_PIECES
+            && !tablebase.solvable(board)
+        {
+            self.stats.tablebase_prunes += 1;
+            return Some(Visit::Failed);
+        }
+
+        self.stats.expanded += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.trail.len());
         let mut moves = Vec::new();
-        list_capture_pairs_into(board, &mut moves);
+        match &mut self.shuffle {
+            Some(rng) => {
+                list_capture_pairs_into(board, &self.rules, &mut moves);
+                rng.shuffle(&mut moves);
+                order_captures(&mut moves);
+            }
+            None => list_ordered_capture_pairs_into(board, &self.rules, &mut moves),
+        }
+        Some(Visit::Open(moves))
+    }
+
+    fn apply(&mut self, step: Step) {
+        self.trail.push(Undo::apply(&mut self.board, step));
+    }
 
-        if moves.is_empty() {
-            dead.insert(*board);
-            return false;
+    fn undo(&mut self) {
+        self.trail.pop().unwrap().revert(&mut self.board);
+    }
+
+    fn found(&mut self) -> Resume {
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= 1;
         }
+        Resume::Found(self.trail.iter().map(|u| u.step).collect())
+    }
+}
 
-        let mut cnt = [0u8; 64];
-        for m in moves.iter() {
-            let (fr, fc) = m.from;
-            cnt[fr * 8 + fc] = cnt[fr * 8 + fc].saturating_add(1);
+impl Iterator for Solutions {
+    type Item = Vec<Step>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.resume(u64::MAX) {
+            Resume::Found(steps) => Some(steps),
+            Resume::Paused | Resume::Finished => None,
         }
+    }
+}
 
-        moves.sort_by_key(|m| {
-            let (fr, fc) = m.from;
-            (cnt[fr * 8 + fc], fr * 8 + fc)
-        });
+impl Undo {
+    /// Makes the capture `step` on `board`, remembering how to take it back.
+    fn apply(board: &mut Board, step: Step) -> Self {
+        let (fr, fc) = step.from;
+        let (tr, tc) = step.to;
+        let from_piece = board.get_cell(fr, fc).unwrap();
+        let to_piece = board.get_cell(tr, tc).unwrap();
+
+        board.move_piece(fr, fc, tr, tc);
+        Self {
+            step,
+            from_piece,
+            to_piece,
+        }
+    }
+
+    fn revert(self, board: &mut Board) {
+        board.set_cell(self.step.from.0, self.step.from.1, self.from_piece);
+        board.set_cell(self.step.to.0, self.step.to.1, self.to_piece);
+    }
+}
+
+impl Frame {
+    fn new(moves: Vec<Step>) -> Self {
+        Self {
+            moves,
+            next: 0,
+            solved: false,
+            discrepancies: 0,
+            cut: false,
+        }
+    }
+}
+
+/// How many capture sequences solve a position.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SolutionCount {
+    None,
+    Unique,
+    /// More than one solution. The count saturates at `u64::MAX`.
+    Many(u64),
+}
+
+/// Counts the solutions of `board` without building them, memoizing the count of every board
+/// reached along the way.
+pub fn count_solutions(board: &Board) -> SolutionCount {
+    count_solutions_with(board, &SolverConfig::default())
+        .expect("a search without budgets always finishes")
+}
+
+/// Like [`count_solutions`], but returns `None` if a budget in `config` runs out first.
+pub fn count_solutions_with(board: &Board, config: &SolverConfig) -> Option<SolutionCount> {
+    let rules = &config.rules;
+    let king_required = king_required(board, rules);
+    let mut memo = TranspositionTable::<u64>::with_capacity_mb(config.table_mb, rules);
+    let mut budget = Budget::new(config);
+    let tablebase = config
+        .tablebase
+        .as_deref()
+        .filter(|tablebase| tablebase.covers(board, rules));
+
+    fn count(
+        board: &mut Board,
+        memo: &mut TranspositionTable<u64>,
+        budget: &mut Budget,
+        tablebase: Option<&Tablebase>,
+        rules: &Rules,
+        king_required: bool,
+    ) -> Option<u64> {
+        if !budget.tick() {
+            return None;
+        }
+
+        if let Some(verdict) = settle(board, rules, king_required) {
+            return Some((verdict == Verdict::Solved) as u64);
+        }
+
+        if tablebase.is_some_and(|tablebase| {
+            board.count_pieces() == tablebase::MAX_PIECES && !tablebase.solvable(board)
+        }) {
+            return Some(0);
+        }
+
+        if let Some(n) = memo.get(board) {
+            return Some(n);
+        }
+
+        let mut moves = Vec::new();
+        list_capture_pairs_into(board, rules, &mut moves);
 
-        for step in moves.iter().copied() {
+        let mut total = 0u64;
+        for step in moves {
             let (fr, fc) = step.from;
             let (tr, tc) = step.to;
 
@@ -84,95 +721,148 @@ pub fn solo_chess_solver(board: &mut Board) -> Vec<Step> {
             let to_piece = board.get_cell(tr, tc).unwrap();
 
             board.move_piece(fr, fc, tr, tc);
-            steps.push(step);
-
-            if dfs(board, steps, dead, king_required) {
-                return true;
-            }
-
-            steps.pop();
+            let n = count(board, memo, budget, tablebase, rules, king_required);
             board.set_cell(fr, fc, from_piece);
             board.set_cell(tr, tc, to_piece);
+            total = total.saturating_add(n?);
         }
 
-        dead.insert(*board);
-        false
+        memo.insert(board, total);
+        Some(total)
     }
 
-    if dfs(board, &mut steps, &mut dead, king_required) {
-        steps
-    } else {
-        Vec::new()
-    }
+    let mut board = *board;
+    Some(
+        match count(
+            &mut board,
+            &mut memo,
+            &mut budget,
+            tablebase,
+            rules,
+            king_required,
+        )? {
+            0 => SolutionCount::None,
+            1 => SolutionCount::Unique,
+            n => SolutionCount::Many(n),
+        },
+    )
 }
 
-fn list_capture_pairs_into(board: &Board, out: &mut Vec<Step>) {
-    out.clear();
+/// How a position was decided without expanding it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Verdict {
+    Solved,
+    /// The wrong piece survived, or the King that had to survive was captured.
+    Lost,
+    /// The remaining move budget cannot clear the board.
+    OutOfMoves,
+    /// A piece that cannot move any more is out of every other piece's reach.
+    Stranded,
+    /// No piece can ever get next to the King, so it cannot make the last capture.
+    KingIsolated,
+    /// The pieces fall into groups that can never capture one another.
+    Split,
+    /// No piece that may be the last one left can meet the goal.
+    GoalOutOfReach,
+}
+
+/// Decides `board` without expanding it when possible.
+fn settle(board: &Board, rules: &Rules, king_required: bool) -> Option<Verdict> {
+    let p = board.count_pieces();
 
-    for (r, c, &p) in board.pieces() {
-        if p.move_left() == 0 {
-            continue;
-        }
-        let piece_type = p.piece_type;
-        let move_rules = match piece_type {
-            PieceType::King | PieceType::Queen => &SLIDER_MOVE,
-            PieceType::Bishop => &SLIDER_MOVE[..4],
-            PieceType::Rook => &SLIDER_MOVE[4..],
-            PieceType::Knight => &KNIGHT_MOVE,
-            PieceType::Pawn => &SLIDER_MOVE[..2],
+    if p <= 1 {
+        let solved = board.pieces().next().is_some_and(|(r, c, piece)| {
+            (!king_required || piece.is_king()) && rules.goal.is_met_by(r, c, piece)
+        });
+        return Some(if solved {
+            Verdict::Solved
+        } else {
+            Verdict::Lost
+        });
+    }
+
+    if king_required && !board.has_king() {
+        return Some(Verdict::Lost);
+    }
+
+    if board.sum_move_left() < p - 1 {
+        return Some(Verdict::OutOfMoves);
+    }
+
+    if rules.goal != Goal::default() {
+        let survivors = if king_required {
+            board.of_type(PieceType::King)
+        } else {
+            board.occupied()
         };
-        get_capturable_cells_into(board, r, c, piece_type, move_rules, out);
-    }
-}
-
-fn get_capturable_cells_into(
-    board: &Board,
-    r: usize,
-    c: usize,
-    piece_type: PieceType,
-    move_rules: &[(i32, i32)],
-    out: &mut Vec<Step>,
-) {
-    let is_king = piece_type == PieceType::King;
-    let is_pawn = piece_type == PieceType::Pawn;
-    let is_knight = piece_type == PieceType::Knight;
-
-    for (dr, dc) in move_rules {
-        let mut cr = r as i32;
-        let mut cc = c as i32;
-        loop {
-            cr += dr;
-            cc += dc;
-            if cr < 0 || cr >= 8 || cc < 0 || cc >= 8 {
-                break;
-            }
-            let ur = cr as usize;
-            let uc = cc as usize;
+        if (rules.goal.candidates(board, rules) & survivors).is_empty() {
+            return Some(Verdict::GoalOutOfReach);
+        }
+    }
 
-            if let Some(cell) = board.get_cell(ur, uc) {
-                if cell.is_king() {
-                    break;
-                }
-                out.push(Step {
-                    from: (r, c),
-                    to: (ur, uc),
-                    piece_type,
-                });
-                break;
-            }
+    prune::prune(board, rules, king_required)
+}
 
-            // King, Pawn and Knight can only move one time.
-            if is_king || is_pawn || is_knight {
-                break;
-            }
+/// Lists the capture pairs in search order: pieces with fewer options move first.
+fn list_ordered_capture_pairs_into(board: &Board, rules: &Rules, out: &mut Vec<Step>) {
+    list_capture_pairs_into(board, rules, out);
+    order_captures(out);
+}
+
+/// Moves the captures of pieces with fewer options first. Captures that tie keep the order
+/// they were listed in.
+fn order_captures(out: &mut [Step]) {
+    let mut cnt = [0u8; SIZE];
+    for m in out.iter() {
+        let (fr, fc) = m.from;
+        cnt[fr * STRIDE + fc] = cnt[fr * STRIDE + fc].saturating_add(1);
+    }
+
+    out.sort_by_key(|m| {
+        let (fr, fc) = m.from;
+        cnt[fr * STRIDE + fc]
+    });
+}
+
+fn list_capture_pairs_into(board: &Board, rules: &Rules, out: &mut Vec<Step>) {
+    out.clear();
+
+    let occupied = board.occupied();
+    // Kings always block lines, but may not be captured unless the rules say so.
+    let capturable = if rules.kings_capturable {
+        occupied
+    } else {
+        occupied & !board.of_type(PieceType::King)
+    };
+
+    for from in squares(board.movable()) {
+        let (r, c) = (from / STRIDE, from % STRIDE);
+        let piece_type = board.get_cell(r, c).unwrap().piece_type;
+        let attacked = attacks(
+            board.shape(),
+            piece_type,
+            from,
+            occupied,
+            rules.pawn_direction,
+        );
+        for to in squares(attacked & capturable) {
+            out.push(Step {
+                from: (r, c),
+                to: (to / STRIDE, to % STRIDE),
+                piece_type,
+            });
         }
     }
 }
 
 #[cfg(test)]
 mod test_solo_chess_solver {
+    use std::collections::HashSet;
+
     use super::*;
+    use crate::board::Shape;
     use crate::piece::Piece;
+    use crate::rules::PawnDirection;
 
     macro_rules! board {
         ( $( ($x:expr, $y:expr, $kind:ident) ),* $(,)? ) => {{
@@ -224,13 +914,624 @@ mod test_solo_chess_solver {
             (4, 2, 3, 0, Knight),
             (3, 0, 1, 1, Knight),
         ];
+        assert_eq!(SolveOutcome::Solved(expected), actual);
+    }
+
+    #[test]
+    fn outcome_tells_apart_positions_without_steps() {
+        let mut board = Board::new();
+        assert_eq!(
+            SolveOutcome::InvalidPosition(InvalidPosition::EmptyBoard),
+            solo_chess_solver(&mut board)
+        );
+
+        let mut board = board![(0, 0, King), (7, 7, King), (0, 7, Rook)];
+        assert_eq!(
+            SolveOutcome::InvalidPosition(InvalidPosition::MultipleKings),
+            solo_chess_solver(&mut board)
+        );
+
+        let mut board = board![(4, 4, King)];
+        assert_eq!(SolveOutcome::AlreadySolved, solo_chess_solver(&mut board));
+
+        let mut board = board![(0, 0, Rook), (7, 7, Rook)];
+        assert_eq!(SolveOutcome::Unsolvable, solo_chess_solver(&mut board));
+    }
+
+    #[test]
+    fn unsolvable_positions_are_explained() {
+        let rules = Rules::default();
+        let explain = |board: &Board| {
+            explain_unsolvable(board, &rules).map(|explanation| explanation.describe(8))
+        };
+
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::with_moves(PieceType::Rook, 0));
+        board.set_cell(1, 1, Piece::with_moves(PieceType::Knight, 1));
+        board.set_cell(6, 6, Piece::with_moves(PieceType::Bishop, 1));
+        board.set_cell(7, 7, Piece::with_moves(PieceType::Rook, 0));
+        assert_eq!(
+            Some("total remaining captures (2) < pieces - 1 (3)".to_string()),
+            explain(&board)
+        );
+
+        let board = board![(1, 1, King), (7, 7, Knight), (6, 7, Knight)];
+        assert_eq!(
+            Some("the King on b7 can never be reached by any capture chain".to_string()),
+            explain(&board)
+        );
+
+        let board = board![(0, 0, Pawn), (1, 1, Pawn), (6, 6, Pawn), (7, 7, Pawn)];
+        assert_eq!(
+            Some(Explanation::Split {
+                groups: vec![vec![(0, 0), (1, 1)], vec![(6, 6), (7, 7)]]
+            }),
+            explain_unsolvable(&board, &rules)
+        );
+        assert_eq!(
+            Some(
+                "the pieces split into 2 groups that can never interact: a8, b7; g2, h1"
+                    .to_string()
+            ),
+            explain(&board)
+        );
+
+        let mut board = board![(0, 1, Bishop)];
+        board.set_cell(7, 7, Piece::with_moves(PieceType::Rook, 0));
+        assert_eq!(
+            Some(Explanation::Stranded {
+                square: (7, 7),
+                piece_type: PieceType::Rook
+            }),
+            explain_unsolvable(&board, &rules)
+        );
+
+        let board = board![(0, 0, Rook)];
+        let goal = Rules {
+            goal: Goal {
+                piece_type: Some(PieceType::Knight),
+                square: None,
+            },
+            ..rules
+        };
+        assert_eq!(
+            Some(Explanation::WrongLastPiece),
+            explain_unsolvable(&board, &goal)
+        );
+
+        // Solvable, or only shown to be unsolvable by searching.
+        let board = board![(0, 0, Rook), (0, 7, Rook)];
+        assert_eq!(None, explain_unsolvable(&board, &rules));
+        let board = board![(0, 0, King), (7, 7, King)];
+        assert_eq!(None, explain_unsolvable(&board, &rules));
+    }
+
+    #[test]
+    fn hint_keeps_the_position_solvable() {
+        let mut board = board![
+            (3, 0, Bishop),
+            (1, 1, Knight),
+            (2, 3, Queen),
+            (3, 2, Knight),
+            (3, 5, Knight),
+            (4, 2, Knight),
+            (5, 5, Bishop),
+            (6, 1, Rook),
+        ];
+        while board.count_pieces() > 1 {
+            let step = next_hint(&board).unwrap();
+            board.move_piece(step.from.0, step.from.1, step.to.0, step.to.1);
+        }
+        assert_eq!(None, next_hint(&board));
+
+        let board = board![(0, 0, Rook), (7, 7, Rook)];
+        assert_eq!(None, next_hint(&board));
+    }
+
+    #[test]
+    fn enumerate_every_solution() {
+        // Either rook can take the other.
+        let board = board![(0, 0, Rook), (0, 7, Rook)];
+        assert_eq!(
+            vec![steps![(0, 0, 0, 7, Rook)], steps![(0, 7, 0, 0, Rook)],],
+            solve_all(&board, None).collect::<Vec<_>>()
+        );
+
+        // The King must survive, so only one order works.
+        let board = board![(0, 0, King), (0, 1, Rook)];
+        assert_eq!(
+            vec![steps![(0, 0, 0, 1, King)]],
+            solve_all(&board, None).collect::<Vec<_>>()
+        );
+
+        let board = board![(0, 0, Rook), (7, 7, Rook)];
+        assert_eq!(0, solve_all(&board, None).count());
+    }
+
+    #[test]
+    fn enumerate_solutions_up_to_cap() {
+        let board = board![(0, 0, Rook), (0, 7, Rook)];
+        assert_eq!(1, solve_all(&board, Some(1)).count());
+        assert_eq!(0, solve_all(&board, Some(0)).count());
+    }
+
+    #[test]
+    fn tiny_table_gives_the_same_answers() {
+        let board = board![
+            (3, 0, Bishop),
+            (1, 1, Knight),
+            (2, 3, Queen),
+            (3, 2, Knight),
+            (3, 5, Knight),
+            (4, 2, Knight),
+            (5, 5, Bishop),
+            (6, 1, Rook),
+        ];
+        let tiny = SolverConfig {
+            table_mb: 0,
+            ..SolverConfig::default()
+        };
+
+        assert_eq!(
+            solve_all(&board, None).count(),
+            solve_all_with(&board, None, &tiny).count()
+        );
+        assert_eq!(
+            Some(count_solutions(&board)),
+            count_solutions_with(&board, &tiny)
+        );
+        let mut scratch = board;
+        assert_eq!(solve_with(&board, &tiny), solo_chess_solver(&mut scratch));
+    }
+
+    #[test]
+    fn budgets_abort_the_search() {
+        let board = board![
+            (3, 0, Bishop),
+            (1, 1, Knight),
+            (2, 3, Queen),
+            (3, 2, Knight),
+            (3, 5, Knight),
+            (4, 2, Knight),
+            (5, 5, Bishop),
+            (6, 1, Rook),
+        ];
+
+        let config = SolverConfig {
+            max_nodes: Some(5),
+            ..SolverConfig::default()
+        };
+        assert_eq!(
+            SolveOutcome::Aborted { nodes_searched: 5 },
+            solve_with(&board, &config)
+        );
+        assert_eq!(None, count_solutions_with(&board, &config));
+
+        let config = SolverConfig {
+            deadline: Some(Instant::now()),
+            ..SolverConfig::default()
+        };
+        assert!(matches!(
+            solve_with(&board, &config),
+            SolveOutcome::Aborted { .. }
+        ));
+
+        let config = SolverConfig::default();
+        config
+            .cancel
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        let mut solutions = solve_all_with(&board, None, &config);
+        assert_eq!(None, solutions.next());
+        assert!(solutions.aborted());
+    }
+
+    #[test]
+    fn task_runs_in_slices() {
+        let board = board![
+            (3, 0, Bishop),
+            (1, 1, Knight),
+            (2, 3, Queen),
+            (3, 2, Knight),
+            (3, 5, Knight),
+            (4, 2, Knight),
+            (5, 5, Bishop),
+            (6, 1, Rook),
+        ];
+
+        let mut task = SolveTask::new(&board, &SolverConfig::default());
+        assert_eq!(None, task.run(0));
+        assert_eq!(Progress::default(), task.progress());
+
+        let mut slices = 0;
+        let outcome = loop {
+            slices += 1;
+            if let Some(outcome) = task.run(3) {
+                break outcome;
+            }
+            assert!(task.progress().nodes <= slices * 3);
+        };
+        assert!(slices > 1);
+        assert_eq!(solve_with(&board, &SolverConfig::default()), outcome);
+        assert_eq!(Some(outcome), task.run(3));
+
+        let mut task = SolveTask::new(&Board::new(), &SolverConfig::default());
+        assert_eq!(
+            Some(SolveOutcome::InvalidPosition(InvalidPosition::EmptyBoard)),
+            task.run(0)
+        );
+    }
+
+    #[test]
+    fn stats_count_the_search() {
+        // Neither rook can capture, so only the root is expanded.
+        let board = board![(0, 0, Rook), (7, 7, Rook)];
+        let (_, stats) = solve_with_stats(&board, &SolverConfig::default());
+        assert_eq!(1, stats.expanded);
+        assert_eq!(0, stats.max_depth);
+
+        let board = board![
+            (0, 0, King),
+            (0, 1, Rook),
+            (0, 2, Rook),
+            (0, 3, Rook),
+            (0, 4, Rook),
+            (0, 5, Rook),
+        ];
+        let (outcome, stats) = solve_with_stats(&board, &SolverConfig::default());
+        assert_eq!(5, outcome.steps().len());
+        assert!(stats.budget_prunes > 0);
+        assert!(stats.memo_hits > 0);
+        assert_eq!(4, stats.max_depth);
+    }
+
+    #[test]
+    fn reachability_prunes_are_counted() {
+        let config = SolverConfig::default();
+
+        let mut board = board![(0, 1, Bishop), (0, 0, Knight)];
+        board.set_cell(7, 7, Piece::with_moves(PieceType::Rook, 0));
+        let (outcome, stats) = solve_with_stats(&board, &config);
+        assert_eq!(SolveOutcome::Unsolvable, outcome);
+        assert_eq!((1, 0), (stats.stranded_prunes, stats.expanded));
+
+        let board = board![(0, 0, King), (7, 7, Knight)];
+        let (outcome, stats) = solve_with_stats(&board, &config);
+        assert_eq!(SolveOutcome::Unsolvable, outcome);
+        assert_eq!((1, 0), (stats.king_prunes, stats.expanded));
+
+        let board = board![(7, 7, Pawn), (6, 6, Pawn), (1, 1, Pawn), (0, 0, Pawn)];
+        let (outcome, stats) = solve_with_stats(&board, &config);
+        assert_eq!(SolveOutcome::Unsolvable, outcome);
+        assert_eq!((1, 0), (stats.split_prunes, stats.expanded));
+    }
+
+    #[test]
+    fn rules_change_the_answer() {
+        let with = |rules: Rules| SolverConfig {
+            rules,
+            ..SolverConfig::default()
+        };
+
+        // Only the Rook can capture, and it has to take every Knight in turn.
+        let mut board = board![
+            (0, 0, Rook),
+            (0, 7, Knight),
+            (7, 7, Knight),
+            (7, 0, Knight),
+            (3, 0, Knight),
+        ];
+        for (capture_limit, solved) in [(Some(1), false), (Some(3), false), (None, true)] {
+            let rules = Rules {
+                capture_limit,
+                ..Rules::default()
+            };
+            board.reset_moves(&rules);
+            let outcome = solve_with(&board, &with(rules));
+            assert_eq!(solved, matches!(outcome, SolveOutcome::Solved(_)));
+            let count = count_solutions_with(&board, &with(rules));
+            assert_eq!(solved, count != Some(SolutionCount::None));
+        }
+
+        let capturable = Rules {
+            kings_capturable: true,
+            ..Rules::default()
+        };
+        let anyone_last = Rules {
+            king_must_survive: false,
+            ..capturable
+        };
+        let board = board![(0, 0, King), (0, 5, Rook)];
+        assert_eq!(
+            SolveOutcome::Unsolvable,
+            solve_with(&board, &with(Rules::default()))
+        );
+        assert_eq!(
+            SolveOutcome::Unsolvable,
+            solve_with(&board, &with(capturable))
+        );
+        assert_eq!(
+            SolveOutcome::Solved(steps![(0, 5, 0, 0, Rook)]),
+            solve_with(&board, &with(anyone_last))
+        );
+
+        let board = board![(0, 0, King), (0, 1, King)];
+        assert_eq!(
+            SolveOutcome::InvalidPosition(InvalidPosition::MultipleKings),
+            solve_with(&board, &with(capturable))
+        );
+        assert_eq!(2, solve_all_with(&board, None, &with(anyone_last)).count());
+
+        let board = board![(1, 1, Pawn), (2, 2, Pawn)];
+        assert_eq!(
+            SolveOutcome::Solved(steps![(2, 2, 1, 1, Pawn)]),
+            solve_with(&board, &with(Rules::default()))
+        );
+        let down = Rules {
+            pawn_direction: PawnDirection::Down,
+            ..Rules::default()
+        };
+        assert_eq!(
+            SolveOutcome::Solved(steps![(1, 1, 2, 2, Pawn)]),
+            solve_with(&board, &with(down))
+        );
+    }
+
+    #[test]
+    fn pieces_keep_their_own_budgets() {
+        let mut board = Board::new();
+        board.set_cell(0, 3, Piece::with_moves(PieceType::Rook, 0));
+        board.set_cell(0, 7, Piece::with_moves(PieceType::Rook, 0));
+
+        board.set_cell(0, 0, Piece::with_moves(PieceType::Queen, 1));
+        assert_eq!(
+            SolveOutcome::Unsolvable,
+            solve_with(&board, &SolverConfig::default())
+        );
+
+        board.set_cell(0, 0, Piece::with_moves(PieceType::Queen, 3));
+        assert_eq!(
+            SolveOutcome::Solved(steps![(0, 0, 0, 3, Queen), (0, 3, 0, 7, Queen)]),
+            solve_with(&board, &SolverConfig::default())
+        );
+    }
+
+    #[test]
+    fn goal_picks_the_last_piece() {
+        let with = |goal: Goal| SolverConfig {
+            rules: Rules {
+                goal,
+                ..Rules::default()
+            },
+            ..SolverConfig::default()
+        };
+
+        // Level 7 again, which can finish with a Knight, Rook or Bishop.
+        let board = board![
+            (3, 0, Bishop),
+            (1, 1, Knight),
+            (2, 3, Queen),
+            (3, 2, Knight),
+            (3, 5, Knight),
+            (4, 2, Knight),
+            (5, 5, Bishop),
+            (6, 1, Rook),
+        ];
+        let finish_with = |piece_type, square| {
+            let goal = Goal { piece_type, square };
+            let solutions: Vec<Vec<Step>> = solve_all_with(&board, None, &with(goal)).collect();
+            for steps in &solutions {
+                let mut b = board;
+                for step in steps {
+                    b.move_piece(step.from.0, step.from.1, step.to.0, step.to.1);
+                }
+                let (r, c, piece) = b.pieces().next().unwrap();
+                assert!(goal.is_met_by(r, c, piece));
+            }
+            solutions.len()
+        };
+        assert_eq!(1468, finish_with(None, None));
+        assert_eq!(1108, finish_with(Some(PieceType::Knight), None));
+        assert_eq!(1260, finish_with(None, Some((1, 1))));
+        assert_eq!(180, finish_with(Some(PieceType::Rook), Some((1, 1))));
+        assert_eq!(0, finish_with(Some(PieceType::Queen), None));
+
+        // There is no King to finish with, so the search stops at the root.
+        let goal = Goal {
+            piece_type: Some(PieceType::King),
+            square: None,
+        };
+        let (outcome, stats) = solve_with_stats(&board, &with(goal));
+        assert_eq!(SolveOutcome::Unsolvable, outcome);
+        assert_eq!((1, 0), (stats.goal_prunes, stats.expanded));
+
+        // A lone piece only counts as solved if it meets the goal.
+        let board = board![(3, 3, Rook)];
+        let goal = Goal {
+            piece_type: Some(PieceType::Rook),
+            square: Some((3, 3)),
+        };
+        assert_eq!(SolveOutcome::AlreadySolved, solve_with(&board, &with(goal)));
+        let goal = Goal {
+            piece_type: Some(PieceType::Queen),
+            square: None,
+        };
+        assert_eq!(SolveOutcome::Unsolvable, solve_with(&board, &with(goal)));
+    }
+
+    #[test]
+    fn goal_square_tells_mirror_images_apart() {
+        // The board is its own mirror image, but the goal square is not, so a dead end on one
+        // side says nothing about the other.
+        let board = board![
+            (0, 2, Queen),
+            (0, 3, Knight),
+            (0, 4, Knight),
+            (0, 5, Queen),
+            (1, 3, Pawn),
+            (1, 4, Pawn),
+        ];
+        let config = SolverConfig {
+            rules: Rules {
+                goal: Goal {
+                    piece_type: None,
+                    square: Some((1, 3)),
+                },
+                ..Rules::default()
+            },
+            ..SolverConfig::default()
+        };
+        assert!(matches!(
+            solve_with(&board, &config),
+            SolveOutcome::Solved(_)
+        ));
+        assert_eq!(14, solve_all_with(&board, None, &config).count());
+        assert_eq!(
+            Some(SolutionCount::Many(14)),
+            count_solutions_with(&board, &config)
+        );
+    }
+
+    #[test]
+    fn fairy_pieces_solve_like_any_other() {
+        // The Amazon can take either Knight first, as a Queen or as a Knight.
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::new(PieceType::Amazon));
+        board.set_cell(1, 2, Piece::with_moves(PieceType::Knight, 0));
+        board.set_cell(3, 3, Piece::with_moves(PieceType::Knight, 0));
+        assert_eq!(SolutionCount::Many(2), count_solutions(&board));
+
+        // The Grasshopper hops over the Rook to take the Camel, or the Rook does all the work.
+        let mut board = Board::new();
+        board.set_cell(7, 0, Piece::new(PieceType::Grasshopper));
+        board.set_cell(6, 0, Piece::new(PieceType::Rook));
+        board.set_cell(5, 0, Piece::with_moves(PieceType::Camel, 0));
+        let solutions: Vec<Vec<Step>> = solve_all(&board, None).collect();
+        assert_eq!(3, solutions.len());
+        assert!(solutions.contains(&steps![(7, 0, 5, 0, Grasshopper), (6, 0, 5, 0, Rook)]));
+    }
+
+    #[test]
+    fn count_solutions_and_check_uniqueness() {
+        let board = board![(0, 0, Rook), (7, 7, Rook)];
+        assert_eq!(SolutionCount::None, count_solutions(&board));
+
+        let board = board![(0, 0, King), (0, 1, Rook)];
+        assert_eq!(SolutionCount::Unique, count_solutions(&board));
+
+        let board = board![(0, 0, Rook), (0, 7, Rook)];
+        assert_eq!(SolutionCount::Many(2), count_solutions(&board));
+
+        let board = board![
+            (3, 0, Bishop),
+            (1, 1, Knight),
+            (2, 3, Queen),
+            (3, 2, Knight),
+            (3, 5, Knight),
+            (4, 2, Knight),
+            (5, 5, Bishop),
+            (6, 1, Rook),
+        ];
+        let n = solve_all(&board, None).count() as u64;
+        assert_eq!(SolutionCount::Many(n), count_solutions(&board));
+    }
+
+    #[test]
+    fn enumerate_matches_search_without_memoization() {
+        // Level 7 again: the dead set must not hide any solution once the search carries on
+        // past the first one.
+        let board = board![
+            (3, 0, Bishop),
+            (1, 1, Knight),
+            (2, 3, Queen),
+            (3, 2, Knight),
+            (3, 5, Knight),
+            (4, 2, Knight),
+            (5, 5, Bishop),
+            (6, 1, Rook),
+        ];
+
+        fn brute_force(board: &mut Board, steps: &mut Vec<Step>, out: &mut HashSet<Vec<Step>>) {
+            if board.count_pieces() == 1 {
+                out.insert(steps.clone());
+                return;
+            }
+            let mut moves = Vec::new();
+            list_capture_pairs_into(board, &Rules::default(), &mut moves);
+            for step in moves {
+                let saved = *board;
+                board.move_piece(step.from.0, step.from.1, step.to.0, step.to.1);
+                steps.push(step);
+                brute_force(board, steps, out);
+                steps.pop();
+                *board = saved;
+            }
+        }
+
+        let mut expected = HashSet::new();
+        let mut scratch = board;
+        brute_force(&mut scratch, &mut Vec::new(), &mut expected);
+
+        let actual: Vec<Vec<Step>> = solve_all(&board, None).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(expected.len(), actual.len());
+        assert_eq!(expected, actual.into_iter().collect());
+    }
+
+    #[test]
+    fn mirrored_board_has_mirrored_solutions() {
+        // The memo tables share entries between mirror images, which must not lose anything.
+        let board = board![
+            (3, 0, Bishop),
+            (1, 1, Knight),
+            (2, 3, Queen),
+            (3, 2, Knight),
+            (3, 5, Knight),
+            (4, 2, Knight),
+            (5, 5, Bishop),
+            (6, 1, Rook),
+        ];
+        let expected: HashSet<Vec<Step>> = solve_all(&board, None)
+            .map(|steps| {
+                steps
+                    .into_iter()
+                    .map(|step| step.mirrored(board.shape()))
+                    .collect()
+            })
+            .collect();
+        let actual: HashSet<Vec<Step>> = solve_all(&board.mirrored(), None).collect();
+        assert!(!expected.is_empty());
         assert_eq!(expected, actual);
+        assert_eq!(count_solutions(&board), count_solutions(&board.mirrored()));
+    }
+
+    #[test]
+    fn rectangular_boards() {
+        let mut board = Board::with_shape(Shape::new(3, 5).unwrap());
+        board.set_cell(0, 4, Piece::new(PieceType::Knight));
+        board.set_cell(2, 3, Piece::new(PieceType::Knight));
+        board.set_cell(1, 2, Piece::new(PieceType::Rook));
+        board.set_cell(1, 1, Piece::with_moves(PieceType::Bishop, 0));
+        let solutions: HashSet<Vec<Step>> = solve_all(&board, None).collect();
+        assert_eq!(3, solutions.len());
+
+        let shape = board.shape();
+        let mirrored: HashSet<Vec<Step>> = solve_all(&board.mirrored(), None)
+            .map(|steps| steps.into_iter().map(|step| step.mirrored(shape)).collect())
+            .collect();
+        assert_eq!(solutions, mirrored);
+
+        // Every capture lands on a piece, so a bigger board adds no solutions.
+        let wide: HashSet<Vec<Step>> = solve_all(&board.resized(Shape::STANDARD), None).collect();
+        assert_eq!(solutions, wide);
     }
 }
 
 #[cfg(test)]
 mod test_utilities {
+    use std::collections::HashSet;
+
     use super::*;
+    use crate::board::Shape;
     use crate::piece::Piece;
 
     fn step(fr: usize, fc: usize, tr: usize, tc: usize, piece_type: PieceType) -> Step {
@@ -253,10 +1554,31 @@ mod test_utilities {
         board.move_piece(0, 0, 4, 4);
 
         let mut capture_pairs = Vec::new();
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert!(capture_pairs.is_empty());
     }
 
+    #[test]
+    fn get_all_capture_pairs_on_the_largest_board() {
+        let shape = Shape::new(Shape::MAX, Shape::MAX).unwrap();
+        let mut board = Board::with_shape(shape);
+        board.set_cell(15, 15, Piece::new(PieceType::Queen));
+        board.set_cell(0, 0, Piece::new(PieceType::Bishop));
+        board.set_cell(0, 15, Piece::with_moves(PieceType::Knight, 0));
+        board.set_cell(13, 14, Piece::with_moves(PieceType::Rook, 0));
+
+        let mut capture_pairs = Vec::new();
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
+        assert_eq!(
+            HashSet::from([
+                step(0, 0, 15, 15, PieceType::Bishop),
+                step(15, 15, 0, 0, PieceType::Queen),
+                step(15, 15, 0, 15, PieceType::Queen),
+            ]),
+            capture_pairs.into_iter().collect()
+        );
+    }
+
     #[test]
     fn get_all_capture_pairs_for_knight() {
         let mut board = Board::new();
@@ -264,12 +1586,12 @@ mod test_utilities {
 
         // No capturable pieces.
         board.set_cell(4, 4, Piece::new(PieceType::Knight));
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert!(capture_pairs.is_empty());
 
         // Should not capture pieces that need two Knight moves.
         board.set_cell(0, 6, Piece::new(PieceType::Pawn));
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert!(capture_pairs.is_empty());
 
         // Pieces can be captured in all direction of the Knight, but one of them is the King.
@@ -282,10 +1604,10 @@ mod test_utilities {
         board.set_cell(2, 3, Piece::new(PieceType::Pawn));
         board.set_cell(6, 3, Piece::new(PieceType::King));
 
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
 
         assert_vec_eq_unordered(
-            &vec![
+            &[
                 step(4, 4, 5, 6, PieceType::Knight),
                 step(4, 4, 5, 2, PieceType::Knight),
                 step(4, 4, 3, 6, PieceType::Knight),
@@ -304,6 +1626,57 @@ mod test_utilities {
         );
     }
 
+    #[test]
+    fn get_all_capture_pairs_for_fairy_pieces() {
+        // Spent pieces only get captured, so every capture comes from the piece under test.
+        let targets = |squares: &[(usize, usize)]| {
+            let mut board = Board::new();
+            for &(r, c) in squares {
+                board.set_cell(r, c, Piece::with_moves(PieceType::Pawn, 0));
+            }
+            board
+        };
+        let captures = |board: &Board, piece_type| {
+            let mut board = *board;
+            board.set_cell(4, 4, Piece::new(piece_type));
+            let mut capture_pairs = Vec::new();
+            list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
+            let mut to: Vec<(usize, usize)> = capture_pairs.iter().map(|s| s.to).collect();
+            to.sort();
+            to
+        };
+
+        // A Bishop line, a Knight leap, a Rook line and a Camel leap.
+        let board = targets(&[(2, 2), (2, 5), (4, 6), (1, 5)]);
+        assert_eq!(
+            vec![(2, 2), (2, 5)],
+            captures(&board, PieceType::Archbishop)
+        );
+        assert_eq!(
+            vec![(2, 5), (4, 6)],
+            captures(&board, PieceType::Chancellor)
+        );
+        assert_eq!(
+            vec![(2, 2), (2, 5), (4, 6)],
+            captures(&board, PieceType::Amazon)
+        );
+        assert_eq!(vec![(1, 5)], captures(&board, PieceType::Camel));
+
+        // The Nightrider repeats Knight leaps, stopping at the first piece.
+        let board = targets(&[(6, 5), (0, 6), (2, 3), (0, 2)]);
+        assert_eq!(
+            vec![(0, 6), (2, 3), (6, 5)],
+            captures(&board, PieceType::Nightrider)
+        );
+
+        // The Grasshopper lands just beyond the first piece on a line, and needs a piece there.
+        let board = targets(&[(4, 5), (4, 6), (2, 2), (1, 1), (6, 6), (0, 4)]);
+        assert_eq!(
+            vec![(1, 1), (4, 6)],
+            captures(&board, PieceType::Grasshopper)
+        );
+    }
+
     #[test]
     fn get_all_capture_paris_for_queen_and_king() {
         let mut board = Board::new();
@@ -311,19 +1684,19 @@ mod test_utilities {
 
         // No capturable pieces
         board.set_cell(5, 4, Piece::new(PieceType::Queen));
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert!(capture_pairs.is_empty());
 
         // The check on that direction should stop if met a King.
         board.set_cell(5, 0, Piece::new(PieceType::Pawn));
         board.set_cell(5, 2, Piece::new(PieceType::King));
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert!(capture_pairs.is_empty());
 
         // Two pawns are aligned on one of the queen’s lines of attack. It should capture only the closest one.
         board.set_cell(5, 0, Piece::new(PieceType::Pawn));
         board.set_cell(5, 2, Piece::new(PieceType::Pawn));
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert_eq!(vec![step(5, 4, 5, 2, PieceType::Queen)], capture_pairs);
 
         /*
@@ -345,10 +1718,10 @@ mod test_utilities {
         board.set_cell(7, 4, Piece::new(PieceType::Pawn));
         board.set_cell(7, 2, Piece::new(PieceType::King));
 
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
 
         assert_vec_eq_unordered(
-            &vec![
+            &[
                 step(5, 4, 3, 2, PieceType::Queen),
                 step(5, 4, 4, 4, PieceType::Queen),
                 step(5, 4, 2, 7, PieceType::Queen),
@@ -362,8 +1735,8 @@ mod test_utilities {
 
         // Replace the queen with a king. Only adjacent pieces should be capturable.
         board.set_cell(5, 4, Piece::new(PieceType::King));
-        list_capture_pairs_into(&board, &mut capture_pairs);
-        assert_vec_eq_unordered(&vec![step(5, 4, 4, 4, PieceType::King)], &capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
+        assert_vec_eq_unordered(&[step(5, 4, 4, 4, PieceType::King)], &capture_pairs);
     }
 
     #[test]
@@ -372,17 +1745,17 @@ mod test_utilities {
         let mut capture_pairs = Vec::new();
 
         board.set_cell(4, 4, Piece::new(PieceType::Pawn));
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert!(capture_pairs.is_empty());
 
         board.set_cell(3, 3, Piece::new(PieceType::Pawn));
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert_eq!(vec![step(4, 4, 3, 3, PieceType::Pawn)], capture_pairs);
 
         board.set_cell(3, 5, Piece::new(PieceType::Pawn));
-        list_capture_pairs_into(&board, &mut capture_pairs);
+        list_capture_pairs_into(&board, &Rules::default(), &mut capture_pairs);
         assert_vec_eq_unordered(
-            &vec![
+            &[
                 step(4, 4, 3, 3, PieceType::Pawn),
                 step(4, 4, 3, 5, PieceType::Pawn),
             ],
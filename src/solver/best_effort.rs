@@ -0,0 +1,326 @@
+use web_time::Instant;
+
+use super::budget::Budget;
+use super::table::TranspositionTable;
+use super::{Frame, Progress, SolveStats, SolverConfig, Undo, list_ordered_capture_pairs_into};
+use crate::{board::Board, step::Step};
+
+/// The closest an unsolvable position comes to being solved.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BestEffort {
+    /// Captures that leave the fewest pieces.
+    pub steps: Vec<Step>,
+    pub pieces_left: usize,
+    /// Whether the captures leave a single piece that meets the goal.
+    pub goal_met: bool,
+    /// Whether a King that started on the board is still there at the end.
+    pub king_survives: bool,
+    /// Whether the whole search finished, so no capture sequence leaves fewer pieces. A
+    /// search stopped by a budget reports the best it had found so far.
+    pub proven: bool,
+}
+
+/// Finds the capture sequence that leaves the fewest pieces on `board`, preferring one whose
+/// last piece meets the goal, and then one that keeps the King when there is one.
+pub fn best_effort(board: &Board) -> BestEffort {
+    best_effort_with(board, &SolverConfig::default())
+}
+
+pub fn best_effort_with(board: &Board, config: &SolverConfig) -> BestEffort {
+    let mut task = BestEffortTask::new(board, config);
+    loop {
+        if let Some(best) = task.run(u64::MAX) {
+            return best;
+        }
+    }
+}
+
+/// Search for [`best_effort`] that can be run a slice at a time, like [`super::SolveTask`].
+pub struct BestEffortTask {
+    board: Board,
+    config: SolverConfig,
+    had_king: bool,
+    /// Positions whose whole subtree has been searched. The best position in such a subtree
+    /// was already compared against `best` on the first visit, so a second visit cannot
+    /// improve on it.
+    explored: TranspositionTable<()>,
+    budget: Budget,
+    stats: SolveStats,
+    trail: Vec<Undo>,
+    stack: Vec<Frame>,
+    best: Option<(Score, Vec<Step>)>,
+    started: bool,
+    done: bool,
+}
+
+/// Pieces left, then whether a lone last piece misses the goal, then whether the King was lost.
+/// Lower is better.
+type Score = (usize, bool, bool);
+
+impl BestEffortTask {
+    pub fn new(board: &Board, config: &SolverConfig) -> Self {
+        Self {
+            board: *board,
+            config: config.clone(),
+            had_king: board.has_king(),
+            explored: TranspositionTable::with_capacity_mb(config.table_mb, &config.rules),
+            budget: Budget::new(config),
+            stats: SolveStats::default(),
+            trail: Vec::new(),
+            stack: Vec::new(),
+            best: None,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Searches at most `slice` more nodes. Returns the result once the search has finished
+    /// or a budget has run out.
+    pub fn run(&mut self, slice: u64) -> Option<BestEffort> {
+        if !self.done {
+            let start = Instant::now();
+            self.done = self.search(slice);
+            self.stats.elapsed += start.elapsed();
+        }
+        self.done.then(|| self.result())
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            nodes: self.budget.nodes(),
+            depth: self.trail.len(),
+            dead_positions: self.explored.len(),
+        }
+    }
+
+    pub fn stats(&self) -> SolveStats {
+        self.stats
+    }
+
+    fn result(&self) -> BestEffort {
+        let (score, steps) = self
+            .best
+            .clone()
+            .unwrap_or_else(|| (self.score(), Vec::new()));
+        BestEffort {
+            steps,
+            pieces_left: score.0,
+            goal_met: score.0 == 1 && !score.1,
+            king_survives: self.had_king && !score.2,
+            proven: !self.budget.exhausted(),
+        }
+    }
+
+    /// Returns whether the search is over.
+    fn search(&mut self, slice: u64) -> bool {
+        let pause_at = self.budget.nodes().saturating_add(slice);
+
+        if !self.started {
+            if slice == 0 {
+                return false;
+            }
+            self.started = true;
+            match self.visit() {
+                Some(moves) if !moves.is_empty() => self.stack.push(Frame::new(moves)),
+                _ => return true,
+            }
+        }
+
+        while let Some(frame) = self.stack.last_mut() {
+            let Some(&step) = frame.moves.get(frame.next) else {
+                self.stack.pop();
+                self.explored.insert(&self.board, ());
+                if !self.stack.is_empty() {
+                    self.undo();
+                }
+                continue;
+            };
+            if self.budget.nodes() >= pause_at {
+                return false;
+            }
+            frame.next += 1;
+
+            self.apply(step);
+            match self.visit() {
+                Some(moves) if !moves.is_empty() => self.stack.push(Frame::new(moves)),
+                Some(_) => self.undo(),
+                None => return true,
+            }
+            if self
+                .best
+                .as_ref()
+                .is_some_and(|(score, _)| *score == (1, false, false))
+            {
+                return true;
+            }
+        }
+
+        true
+    }
+
+    /// Records the current position if it beats the best so far, and lists the captures worth
+    /// trying from it. Returns `None` once a budget has run out.
+    fn visit(&mut self) -> Option<Vec<Step>> {
+        if !self.budget.tick() {
+            return None;
+        }
+
+        let score = self.score();
+        if self.best.as_ref().is_none_or(|(best, _)| score < *best) {
+            self.best = Some((score, self.trail.iter().map(|u| u.step).collect()));
+        }
+
+        if self.explored.contains(&self.board) {
+            self.stats.memo_hits += 1;
+            return Some(Vec::new());
+        }
+
+        // Every capture takes one piece off, so no line from here can leave fewer pieces than
+        // the moves left allow, and a lost King stays lost.
+        let (pieces, _, king_lost) = score;
+        let floor = pieces - self.board.sum_move_left().min(pieces.saturating_sub(1));
+        if self
+            .best
+            .as_ref()
+            .is_some_and(|(best, _)| *best <= (floor, false, king_lost))
+        {
+            self.stats.budget_prunes += 1;
+            return Some(Vec::new());
+        }
+
+        self.stats.expanded += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.trail.len());
+        let mut moves = Vec::new();
+        list_ordered_capture_pairs_into(&self.board, &self.config.rules, &mut moves);
+        Some(moves)
+    }
+
+    fn score(&self) -> Score {
+        let pieces = self.board.count_pieces();
+        let goal_missed = pieces == 1
+            && self
+                .board
+                .pieces()
+                .any(|(r, c, p)| !self.config.rules.goal.is_met_by(r, c, p));
+        (pieces, goal_missed, self.had_king && !self.board.has_king())
+    }
+
+    fn apply(&mut self, step: Step) {
+        self.trail.push(Undo::apply(&mut self.board, step));
+    }
+
+    fn undo(&mut self) {
+        self.trail.pop().unwrap().revert(&mut self.board);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::piece::{Piece, PieceType};
+    use crate::rules::{Goal, Rules};
+
+    fn step(fr: usize, fc: usize, tr: usize, tc: usize, piece_type: PieceType) -> Step {
+        Step {
+            from: (fr, fc),
+            to: (tr, tc),
+            piece_type,
+        }
+    }
+
+    #[test]
+    fn fewest_pieces_left() {
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::new(PieceType::Rook));
+        board.set_cell(7, 7, Piece::new(PieceType::Rook));
+        let best = best_effort(&board);
+        assert_eq!((2, true), (best.pieces_left, best.proven));
+        assert!(best.steps.is_empty());
+
+        // Still unsolvable, but the spent Rook on a7 can be taken.
+        board.set_cell(1, 0, Piece::with_moves(PieceType::Rook, 0));
+        board.set_cell(6, 6, Piece::with_moves(PieceType::Knight, 0));
+        let best = best_effort(&board);
+        assert_eq!(3, best.pieces_left);
+        assert_eq!(vec![step(0, 0, 1, 0, PieceType::Rook)], best.steps);
+        assert!(!best.king_survives);
+    }
+
+    #[test]
+    fn keeps_the_king_when_it_can() {
+        let rules = Rules {
+            kings_capturable: true,
+            ..Rules::default()
+        };
+        let config = SolverConfig {
+            rules,
+            ..SolverConfig::default()
+        };
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::new(PieceType::Rook));
+        board.set_cell(0, 1, Piece::new(PieceType::King));
+        board.set_cell(7, 7, Piece::with_moves(PieceType::Knight, 0));
+
+        // The Rook taking the King is tried first, and also leaves two pieces.
+        let best = best_effort_with(&board, &config);
+        assert_eq!(2, best.pieces_left);
+        assert!(best.king_survives);
+        assert_eq!(vec![step(0, 1, 0, 0, PieceType::King)], best.steps);
+    }
+
+    #[test]
+    fn meeting_the_goal_comes_before_the_king() {
+        let config = SolverConfig {
+            rules: Rules {
+                kings_capturable: true,
+                king_must_survive: true,
+                goal: Goal {
+                    piece_type: None,
+                    square: Some((0, 0)),
+                },
+                ..Rules::default()
+            },
+            ..SolverConfig::default()
+        };
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::new(PieceType::King));
+        board.set_cell(0, 1, Piece::new(PieceType::Rook));
+
+        // Only the Rook taking the King ends on a8.
+        let best = best_effort_with(&board, &config);
+        assert_eq!(
+            (1, true, false),
+            (best.pieces_left, best.goal_met, best.king_survives)
+        );
+        assert_eq!(vec![step(0, 1, 0, 0, PieceType::Rook)], best.steps);
+
+        // Neither capture ends on a8, so the King is kept.
+        let mut board = Board::new();
+        board.set_cell(0, 1, Piece::new(PieceType::King));
+        board.set_cell(0, 2, Piece::new(PieceType::Rook));
+        let best = best_effort_with(&board, &config);
+        assert_eq!(
+            (1, false, true),
+            (best.pieces_left, best.goal_met, best.king_survives)
+        );
+        assert_eq!(vec![step(0, 1, 0, 2, PieceType::King)], best.steps);
+    }
+
+    #[test]
+    fn budget_keeps_the_best_so_far() {
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::new(PieceType::Rook));
+        board.set_cell(0, 7, Piece::new(PieceType::Rook));
+        board.set_cell(7, 7, Piece::new(PieceType::Rook));
+        board.set_cell(7, 0, Piece::new(PieceType::Rook));
+        board.set_cell(3, 3, Piece::with_moves(PieceType::Bishop, 0));
+        let config = SolverConfig {
+            max_nodes: Some(2),
+            ..SolverConfig::default()
+        };
+        let best = best_effort_with(&board, &config);
+        assert!(!best.proven);
+        assert_eq!(4, best.pieces_left);
+        assert_eq!(1, best.steps.len());
+    }
+}
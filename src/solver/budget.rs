@@ -0,0 +1,54 @@
+use std::sync::atomic::Ordering;
+
+use web_time::Instant;
+
+use super::SolverConfig;
+
+/// Reading the clock and the cancel flag every node would dominate cheap nodes, so they are
+/// only polled this often.
+const POLL_INTERVAL: u64 = 1024;
+
+/// Counts searched nodes and tells the search when to give up.
+pub struct Budget {
+    nodes: u64,
+    config: SolverConfig,
+    exhausted: bool,
+}
+
+impl Budget {
+    pub fn new(config: &SolverConfig) -> Self {
+        Self {
+            nodes: 0,
+            config: config.clone(),
+            exhausted: false,
+        }
+    }
+
+    /// Counts one more node. Returns `false` once the search has to stop.
+    pub fn tick(&mut self) -> bool {
+        if self.exhausted {
+            return false;
+        }
+
+        if self.config.max_nodes.is_some_and(|max| self.nodes >= max) {
+            self.exhausted = true;
+        } else if self.nodes.is_multiple_of(POLL_INTERVAL) {
+            self.exhausted = self.config.cancel.load(Ordering::Relaxed)
+                || self.config.deadline.is_some_and(|d| Instant::now() >= d);
+        }
+        if self.exhausted {
+            return false;
+        }
+
+        self.nodes += 1;
+        true
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+}
@@ -0,0 +1,208 @@
+use super::table::{DeadSet, TranspositionTable};
+use super::{
+    Resume, Solutions, SolveOutcome, SolverConfig, king_required, list_capture_pairs_into,
+    solve_with_stats,
+};
+use crate::board::Board;
+use crate::step::Step;
+
+/// Lowest score of levels 2 to 10. These are rough, hand-picked cut-offs rather than a fit to
+/// chess.com's levels: only one position in the tests has a known level.
+const LEVEL_SCORES: [f64; 9] = [4.0, 5.75, 7.25, 8.5, 9.75, 11.0, 13.0, 15.0, 17.5];
+
+/// How hard a solvable position is, and what went into the rating.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Difficulty {
+    /// From 1, the easiest, to 10, like chess.com's Solo Chess levels.
+    pub level: u8,
+    pub score: f64,
+    pub pieces: usize,
+    /// Average number of captures on offer at each position along the solution.
+    pub branching: f64,
+    /// Share of those captures that leave a position with no solution. It is measured along the
+    /// one solution found, not as dead ends against every solution of the puzzle.
+    pub losing_share: f64,
+    /// Positions along the solution where only one capture keeps it solvable.
+    pub forced_moves: usize,
+    /// Positions the solver expanded to find the solution.
+    pub expanded: u64,
+}
+
+/// Rates a solvable `board`, or returns `None` if it has no solution.
+pub fn rate(board: &Board) -> Option<Difficulty> {
+    rate_with(board, &SolverConfig::default())
+}
+
+/// Like [`rate`], also returning `None` if a budget in `config` runs out first. Every capture
+/// along the solution is tried, so this costs one search per capture on offer.
+pub fn rate_with(board: &Board, config: &SolverConfig) -> Option<Difficulty> {
+    let (outcome, stats) = solve_with_stats(board, config);
+    let SolveOutcome::Solved(solution) = outcome else {
+        return None;
+    };
+    let rules = &config.rules;
+    // A capture that takes the King has to count as a dead end, so every position along the
+    // line is judged by whether the King had to survive at the start.
+    let king_required = king_required(board, rules);
+
+    // Positions found dead by one search stay dead for the next.
+    let mut dead = DeadSet::Local(TranspositionTable::with_capacity_mb(config.table_mb, rules));
+    let mut board = *board;
+    let (mut offered, mut losing, mut forced_moves) = (0, 0, 0);
+    let mut moves = Vec::new();
+    for step in &solution {
+        list_capture_pairs_into(&board, rules, &mut moves);
+        let mut alive = 0;
+        for &capture in &moves {
+            let mut after = board;
+            play(&mut after, capture);
+            let mut search = Solutions::new(&after, king_required, Some(1), config, dead);
+            match search.resume(u64::MAX) {
+                Resume::Found(_) => alive += 1,
+                Resume::Finished if !search.aborted() => losing += 1,
+                _ => return None,
+            }
+            dead = search.dead;
+        }
+        offered += moves.len();
+        forced_moves += (alive == 1) as usize;
+        play(&mut board, *step);
+    }
+
+    let pieces = solution.len() + 1;
+    let branching = offered as f64 / solution.len() as f64;
+    let losing_share = losing as f64 / offered as f64;
+    let score = Difficulty::score(
+        pieces,
+        branching,
+        losing_share,
+        forced_moves,
+        stats.expanded,
+    );
+    Some(Difficulty {
+        level: 1 + LEVEL_SCORES.iter().filter(|&&s| score >= s).count() as u8,
+        score,
+        pieces,
+        branching,
+        losing_share,
+        forced_moves,
+        expanded: stats.expanded,
+    })
+}
+
+impl Difficulty {
+    /// Weighs the features into one number. The weights are picked by hand: every capture
+    /// counts for one point, with more for each capture on offer that loses, for captures
+    /// that are the only way on, and for a larger search.
+    pub fn score(
+        pieces: usize,
+        branching: f64,
+        losing_share: f64,
+        forced_moves: usize,
+        expanded: u64,
+    ) -> f64 {
+        let captures = pieces.saturating_sub(1) as f64;
+        captures
+            + 0.5 * forced_moves as f64
+            + 2.0 * losing_share * branching.ln_1p()
+            + 0.5 * (expanded as f64).ln_1p()
+    }
+}
+
+fn play(board: &mut Board, step: Step) {
+    let ((fr, fc), (tr, tc)) = (step.from, step.to);
+    board.move_piece(fr, fc, tr, tc);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::generate;
+    use crate::piece::{Piece, PieceType};
+    use crate::rules::Rules;
+
+    fn board(pieces: &[(usize, usize, PieceType)]) -> Board {
+        let mut board = Board::new();
+        for &(r, c, piece_type) in pieces {
+            board.set_cell(r, c, Piece::new(piece_type));
+        }
+        board
+    }
+
+    /// Positions whose chess.com level is known. Add more as they are collected, and retune
+    /// [`LEVEL_SCORES`] so they keep their levels.
+    fn corpus() -> Vec<(u8, Board)> {
+        vec![(
+            7,
+            board(&[
+                (3, 0, PieceType::Bishop),
+                (1, 1, PieceType::Knight),
+                (2, 3, PieceType::Queen),
+                (3, 2, PieceType::Knight),
+                (3, 5, PieceType::Knight),
+                (4, 2, PieceType::Knight),
+                (5, 5, PieceType::Bishop),
+                (6, 1, PieceType::Rook),
+            ]),
+        )]
+    }
+
+    #[test]
+    fn known_levels() {
+        for (level, board) in corpus() {
+            assert_eq!(Some(level), rate(&board).map(|d| d.level));
+        }
+    }
+
+    #[test]
+    fn features() {
+        // Either Rook takes the other, and nothing can go wrong.
+        let easy = rate(&board(&[(0, 0, PieceType::Rook), (0, 7, PieceType::Rook)])).unwrap();
+        assert_eq!((1, 2, 2.0, 0.0, 0), {
+            let d = easy;
+            (
+                d.level,
+                d.pieces,
+                d.branching,
+                d.losing_share,
+                d.forced_moves,
+            )
+        });
+
+        // The Rook may not take the King, so the King's capture is forced.
+        let forced = rate(&board(&[(0, 0, PieceType::King), (0, 1, PieceType::Rook)])).unwrap();
+        assert_eq!((1.0, 0.0, 1), {
+            let d = forced;
+            (d.branching, d.losing_share, d.forced_moves)
+        });
+
+        assert_eq!(
+            None,
+            rate(&board(&[(0, 0, PieceType::Rook), (7, 7, PieceType::Rook)]))
+        );
+    }
+
+    #[test]
+    fn more_pieces_rate_higher() {
+        let mix = &[
+            PieceType::King,
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Pawn,
+        ];
+        let average = |n| {
+            (0..10)
+                .map(|seed| {
+                    rate(&generate(n, mix, &Rules::default(), seed))
+                        .unwrap()
+                        .score
+                })
+                .sum::<f64>()
+                / 10.0
+        };
+        let scores: Vec<f64> = [3, 5, 7, 9].into_iter().map(average).collect();
+        assert!(scores.is_sorted(), "{scores:?}");
+    }
+}
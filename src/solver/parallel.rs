@@ -0,0 +1,268 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::table::{DeadSet, SharedTable};
+use super::{
+    Resume, Solutions, SolveOutcome, SolverConfig, Verdict, check_position, king_required,
+    list_ordered_capture_pairs_into, settle,
+};
+use crate::{board::Board, step::Step};
+
+/// How often the coordinating thread checks the caller's cancel flag.
+const CANCEL_POLL: Duration = Duration::from_millis(5);
+
+/// Nodes a worker takes from the shared node budget at a time.
+const BUDGET_SLICE: u64 = 1024;
+
+/// Multi-threaded [`super::solve_with`]. The first solution any thread finds stops the others.
+///
+/// Passing 0 for `threads` uses every available core. `max_nodes` in `config` is one budget
+/// shared by every thread, so the search visits no more positions than [`super::solve_with`]
+/// would.
+pub fn solve_parallel(board: &Board, config: &SolverConfig, threads: usize) -> SolveOutcome {
+    if let Some(outcome) = check_position(board, &config.rules) {
+        return outcome;
+    }
+
+    let run = run(board, Some(1), config, threads, true);
+    match run.solutions.into_iter().next() {
+        Some(steps) => SolveOutcome::Solved(steps),
+        None if run.aborted => SolveOutcome::Aborted {
+            nodes_searched: run.nodes,
+        },
+        None => SolveOutcome::Unsolvable,
+    }
+}
+
+/// Multi-threaded [`super::solve_all_with`]. Yields the same solutions in the same order as the
+/// sequential search, or `None` if a budget ran out first.
+pub fn solve_all_parallel(
+    board: &Board,
+    cap: Option<usize>,
+    config: &SolverConfig,
+    threads: usize,
+) -> Option<Vec<Vec<Step>>> {
+    let run = run(board, cap, config, threads, false);
+    (!run.aborted).then_some(run.solutions)
+}
+
+struct Run {
+    solutions: Vec<Vec<Step>>,
+    aborted: bool,
+    nodes: u64,
+}
+
+/// Splits the root captures between threads. Each root capture is searched by one thread,
+/// and the solutions are put back together in root order.
+fn run(
+    board: &Board,
+    cap: Option<usize>,
+    config: &SolverConfig,
+    threads: usize,
+    first_only: bool,
+) -> Run {
+    let king_required = king_required(board, &config.rules);
+    if let Some(verdict) = settle(board, &config.rules, king_required) {
+        return Run {
+            solutions: if verdict == Verdict::Solved && cap != Some(0) {
+                vec![Vec::new()]
+            } else {
+                Vec::new()
+            },
+            aborted: false,
+            nodes: 1,
+        };
+    }
+
+    let mut roots = Vec::new();
+    list_ordered_capture_pairs_into(board, &config.rules, &mut roots);
+
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+
+    // Workers watch their own flag, so a solution can stop them without touching the caller's.
+    // The node budget is shared between them through `nodes` instead of given to each.
+    let stop = Arc::new(AtomicBool::new(false));
+    let worker_config = SolverConfig {
+        cancel: stop.clone(),
+        max_nodes: None,
+        ..config.clone()
+    };
+    let max_nodes = config.max_nodes.unwrap_or(u64::MAX);
+    let dead = Arc::new(SharedTable::with_capacity_mb(
+        config.table_mb,
+        &config.rules,
+    ));
+    let next_root = AtomicUsize::new(0);
+    let running = AtomicUsize::new(threads);
+    let nodes = AtomicU64::new(1);
+    let completed = AtomicUsize::new(0);
+    let results: Vec<Mutex<Vec<Vec<Step>>>> = roots.iter().map(|_| Mutex::default()).collect();
+
+    thread::scope(|s| {
+        for _ in 0..threads {
+            s.spawn(|| {
+                loop {
+                    let i = next_root.fetch_add(1, Ordering::Relaxed);
+                    if i >= roots.len()
+                        || stop.load(Ordering::Relaxed)
+                        || config.cancel.load(Ordering::Relaxed)
+                    {
+                        break;
+                    }
+
+                    let root = roots[i];
+                    let mut child = *board;
+                    child.move_piece(root.from.0, root.from.1, root.to.0, root.to.1);
+
+                    let shared = DeadSet::Shared(dead.clone());
+                    let mut solutions =
+                        Solutions::new(&child, king_required, cap, &worker_config, shared);
+                    let mut found: Vec<Vec<Step>> = Vec::new();
+                    let out_of_nodes = loop {
+                        // Take a slice of what is left of the budget, and hand back what the
+                        // slice did not use, so the threads never search more than it allows.
+                        let Ok(before) =
+                            nodes.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                                (n < max_nodes).then(|| n + (max_nodes - n).min(BUDGET_SLICE))
+                            })
+                        else {
+                            break true;
+                        };
+                        let taken = (max_nodes - before).min(BUDGET_SLICE);
+                        let searched = solutions.nodes_searched();
+                        let resume = solutions.resume(taken);
+                        let used = solutions.nodes_searched() - searched;
+                        nodes.fetch_sub(taken.saturating_sub(used), Ordering::Relaxed);
+                        match resume {
+                            Resume::Found(tail) => {
+                                found.push(std::iter::once(root).chain(tail).collect())
+                            }
+                            Resume::Paused => {}
+                            Resume::Finished => break false,
+                        }
+                    };
+
+                    if out_of_nodes || solutions.aborted() {
+                        stop.store(true, Ordering::Relaxed);
+                        break;
+                    }
+                    if first_only && !found.is_empty() {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                    *results[i].lock().unwrap() = found;
+                    completed.fetch_add(1, Ordering::Relaxed);
+                }
+                running.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+
+        while running.load(Ordering::Relaxed) > 0 {
+            if config.cancel.load(Ordering::Relaxed) {
+                stop.store(true, Ordering::Relaxed);
+            }
+            thread::sleep(CANCEL_POLL);
+        }
+    });
+
+    let mut solutions: Vec<Vec<Step>> = results
+        .into_iter()
+        .flat_map(|r| r.into_inner().unwrap())
+        .collect();
+    if let Some(cap) = cap {
+        solutions.truncate(cap);
+    }
+
+    // Roots left unsearched only matter if they could still have changed the answer.
+    let finished = completed.into_inner() == roots.len() || (first_only && !solutions.is_empty());
+    Run {
+        aborted: !finished,
+        solutions,
+        nodes: nodes.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::piece::{Piece, PieceType};
+    use crate::solver::{solve_all, solve_with};
+
+    fn level_7() -> Board {
+        let mut board = Board::new();
+        for (r, c, piece_type) in [
+            (3, 0, PieceType::Bishop),
+            (1, 1, PieceType::Knight),
+            (2, 3, PieceType::Queen),
+            (3, 2, PieceType::Knight),
+            (3, 5, PieceType::Knight),
+            (4, 2, PieceType::Knight),
+            (5, 5, PieceType::Bishop),
+            (6, 1, PieceType::Rook),
+        ] {
+            board.set_cell(r, c, Piece::new(piece_type));
+        }
+        board
+    }
+
+    #[test]
+    fn same_solutions_as_sequential() {
+        let board = level_7();
+        let expected: Vec<Vec<Step>> = solve_all(&board, None).collect();
+        for threads in [1, 3] {
+            let actual = solve_all_parallel(&board, None, &SolverConfig::default(), threads);
+            assert_eq!(Some(expected.clone()), actual);
+        }
+
+        let capped = solve_all_parallel(&board, Some(4), &SolverConfig::default(), 2);
+        assert_eq!(Some(expected[..4].to_vec()), capped);
+    }
+
+    #[test]
+    fn first_solution_is_a_solution() {
+        let board = level_7();
+        let all: Vec<Vec<Step>> = solve_all(&board, None).collect();
+        let SolveOutcome::Solved(steps) = solve_parallel(&board, &SolverConfig::default(), 4)
+        else {
+            panic!("Level 7 has a solution");
+        };
+        assert!(all.contains(&steps));
+
+        let mut empty = Board::new();
+        assert_eq!(
+            solve_with(&empty, &SolverConfig::default()),
+            solve_parallel(&empty, &SolverConfig::default(), 2)
+        );
+        empty.set_cell(0, 0, Piece::new(PieceType::Rook));
+        empty.set_cell(7, 7, Piece::new(PieceType::Rook));
+        assert_eq!(
+            SolveOutcome::Unsolvable,
+            solve_parallel(&empty, &SolverConfig::default(), 2)
+        );
+    }
+
+    #[test]
+    fn node_budget_is_shared() {
+        let config = SolverConfig {
+            max_nodes: Some(3000),
+            ..SolverConfig::default()
+        };
+        let run = run(&level_7(), None, &config, 4, false);
+        assert!(run.aborted);
+        assert!(run.nodes <= 3000, "{}", run.nodes);
+    }
+
+    #[test]
+    fn cancel_stops_every_thread() {
+        let config = SolverConfig::default();
+        config.cancel.store(true, Ordering::Relaxed);
+        assert!(matches!(
+            solve_parallel(&level_7(), &config, 2),
+            SolveOutcome::Aborted { .. }
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+use super::Verdict;
+use crate::bitboard::{Bitboard, attacks, reach, squares};
+use crate::board::{Board, STRIDE};
+use crate::piece::{Piece, PieceType};
+use crate::rules::Rules;
+
+/// Where one piece could go.
+pub(super) struct Reach {
+    sq: usize,
+    moves: usize,
+    /// Squares the piece could capture on.
+    reaches: Bitboard,
+    /// Squares the piece could ever stand on.
+    stands: Bitboard,
+}
+
+/// Rules out positions whose pieces can never all meet, judged from where every piece could
+/// possibly go with the moves it has left. Blockers are ignored, so a prune here is always
+/// sound, but not every lost position is caught.
+pub(super) fn prune(board: &Board, rules: &Rules, king_required: bool) -> Option<Verdict> {
+    find_flaw(board, rules, king_required).map(|flaw| match flaw {
+        Flaw::Stranded(_) => Verdict::Stranded,
+        Flaw::KingIsolated(_) => Verdict::KingIsolated,
+        Flaw::Split { .. } => Verdict::Split,
+    })
+}
+
+/// What [`prune`] found, with the pieces to blame.
+pub(super) enum Flaw {
+    /// Square of a piece that cannot move and that nothing can reach.
+    Stranded(usize),
+    /// Square of the King nothing can get next to.
+    KingIsolated(usize),
+    Split {
+        pieces: Vec<Reach>,
+        /// Union-find forest over `pieces`, one tree per group.
+        parent: Vec<usize>,
+    },
+}
+
+impl Flaw {
+    /// Squares of the pieces in each group of a [`Flaw::Split`], or nothing for other flaws.
+    pub(super) fn groups(mut self) -> Vec<Vec<usize>> {
+        let Flaw::Split { pieces, parent } = &mut self else {
+            return Vec::new();
+        };
+        let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+        for (i, piece) in pieces.iter().enumerate() {
+            let root = find(parent, i);
+            match groups.iter_mut().find(|(r, _)| *r == root) {
+                Some((_, squares)) => squares.push(piece.sq),
+                None => groups.push((root, vec![piece.sq])),
+            }
+        }
+        groups.into_iter().map(|(_, squares)| squares).collect()
+    }
+}
+
+pub(super) fn find_flaw(board: &Board, rules: &Rules, king_required: bool) -> Option<Flaw> {
+    let shape = board.shape();
+    let pawns = rules.pawn_direction;
+    let pieces: Vec<Reach> = board
+        .pieces()
+        .map(|(r, c, p)| {
+            let sq = r * STRIDE + c;
+            let reaches = reach(shape, p.piece_type, sq, p.move_left(), pawns);
+            Reach {
+                sq,
+                moves: p.move_left(),
+                reaches,
+                stands: reaches | Bitboard::square(sq),
+            }
+        })
+        .collect();
+    let n = pieces.len();
+    let any_reach = pieces
+        .iter()
+        .fold(Bitboard::EMPTY, |acc, piece| acc | piece.reaches);
+    let kings = board.of_type(PieceType::King);
+    let is_king = |i: usize| kings.contains(pieces[i].sq);
+    // Pieces no solution ever captures. Taking a King that has to survive loses on the spot.
+    let immune = if rules.kings_capturable && !king_required {
+        Bitboard::EMPTY
+    } else {
+        kings
+    };
+    let is_immune = |i: usize| immune.contains(pieces[i].sq);
+
+    // A piece that can no longer move has to be captured where it stands.
+    let stuck = board.occupied() & !board.movable() & !immune;
+    if let Some(sq) = (stuck & !any_reach).first() {
+        return Some(Flaw::Stranded(sq));
+    }
+
+    // A surviving King makes the last capture, so some piece has to come next to it.
+    if king_required && let Some(k) = (0..n).find(|&i| is_king(i)) {
+        let before_last = match pieces[k].moves {
+            0 => None,
+            Piece::UNLIMITED => Some(Piece::UNLIMITED),
+            m => Some(m - 1),
+        };
+        let king = pieces[k].sq;
+        let neighbourhood = before_last.map_or(Bitboard::EMPTY, |m| {
+            squares(reach(shape, PieceType::King, king, m, pawns) | Bitboard::square(king))
+                .fold(Bitboard::EMPTY, |acc, u| {
+                    acc | attacks(shape, PieceType::King, u, Bitboard::EMPTY, pawns)
+                })
+        });
+        if !(0..n).any(|i| i != k && !(pieces[i].stands & neighbourhood).is_empty()) {
+            return Some(Flaw::KingIsolated(king));
+        }
+    }
+
+    // Pieces in different groups can never capture each other, and every group keeps at least
+    // one piece.
+    let mut parent: Vec<usize> = (0..n).collect();
+    let mut groups = n;
+    for i in 0..n {
+        for j in i + 1..n {
+            let (pi, pj) = (&pieces[i], &pieces[j]);
+            let meet = (!is_immune(j) && !(pi.reaches & pj.stands).is_empty())
+                || (!is_immune(i) && !(pj.reaches & pi.stands).is_empty());
+            if !meet {
+                continue;
+            }
+            let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+            if a != b {
+                parent[a] = b;
+                groups -= 1;
+            }
+        }
+    }
+    if groups > 1 {
+        return Some(Flaw::Split { pieces, parent });
+    }
+
+    None
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rules::PawnDirection;
+
+    fn board(pieces: &[(usize, usize, PieceType, usize)]) -> Board {
+        let mut board = Board::new();
+        for &(r, c, piece_type, moves) in pieces {
+            board.set_cell(r, c, Piece::with_moves(piece_type, moves));
+        }
+        board
+    }
+
+    #[test]
+    fn stranded_piece_nobody_can_reach() {
+        // The Rook on h1 is spent. The Bishop is stuck on dark squares and the Knight is too far.
+        let b = board(&[
+            (7, 7, PieceType::Rook, 0),
+            (0, 1, PieceType::Bishop, 2),
+            (0, 0, PieceType::Knight, 2),
+        ]);
+        assert_eq!(Some(Verdict::Stranded), prune(&b, &Rules::default(), false));
+
+        // A Queen can get there in two captures.
+        let b = board(&[(7, 7, PieceType::Rook, 0), (0, 1, PieceType::Queen, 2)]);
+        assert_eq!(None, prune(&b, &Rules::default(), false));
+
+        // Pawns only reach the rows they capture toward.
+        let b = board(&[(0, 0, PieceType::Rook, 0), (1, 1, PieceType::Pawn, 2)]);
+        assert_eq!(None, prune(&b, &Rules::default(), false));
+        let down = Rules {
+            pawn_direction: PawnDirection::Down,
+            ..Rules::default()
+        };
+        assert_eq!(Some(Verdict::Stranded), prune(&b, &down, false));
+    }
+
+    #[test]
+    fn king_nobody_can_approach() {
+        let b = board(&[(0, 0, PieceType::King, 2), (7, 7, PieceType::Knight, 2)]);
+        assert_eq!(
+            Some(Verdict::KingIsolated),
+            prune(&b, &Rules::default(), true)
+        );
+
+        // A King without moves cannot make the last capture.
+        let b = board(&[(0, 0, PieceType::King, 0), (0, 1, PieceType::Rook, 2)]);
+        assert_eq!(
+            Some(Verdict::KingIsolated),
+            prune(&b, &Rules::default(), true)
+        );
+
+        let b = board(&[(0, 0, PieceType::King, 2), (2, 2, PieceType::Knight, 2)]);
+        assert_eq!(None, prune(&b, &Rules::default(), true));
+    }
+
+    #[test]
+    fn pieces_split_into_groups() {
+        // Pawns only move up the board, so the two pairs never meet.
+        let b = board(&[
+            (7, 7, PieceType::Pawn, 2),
+            (6, 6, PieceType::Pawn, 2),
+            (1, 1, PieceType::Pawn, 2),
+            (0, 0, PieceType::Pawn, 2),
+        ]);
+        assert_eq!(Some(Verdict::Split), prune(&b, &Rules::default(), false));
+
+        // A King only links groups when it may be captured.
+        let b = board(&[(0, 0, PieceType::King, 0), (0, 1, PieceType::Rook, 2)]);
+        assert_eq!(Some(Verdict::Split), prune(&b, &Rules::default(), false));
+        let capturable = Rules {
+            kings_capturable: true,
+            ..Rules::default()
+        };
+        assert_eq!(None, prune(&b, &capturable, false));
+
+        let b = board(&[
+            (7, 7, PieceType::Pawn, 2),
+            (6, 6, PieceType::Pawn, 2),
+            (5, 5, PieceType::Pawn, 2),
+        ]);
+        assert_eq!(None, prune(&b, &Rules::default(), false));
+    }
+}
@@ -0,0 +1,670 @@
+//! Poses a puzzle as a SAT problem in conjunctive normal form, to be solved by [`cdcl`] or
+//! written out in DIMACS format for an external solver.
+//!
+//! A puzzle with `n` pieces is solved in exactly `n - 1` captures, so the encoding unrolls that
+//! many steps:
+//!
+//! - `at(i, s, t)`: piece `i` stands on square `s` after `t` captures.
+//! - `capture(i, t, a, b)`: capture `t` is piece `i` taking from `a` onto `b`. Exactly one
+//!   capture is made per step, and a piece moves only when it captures.
+//! - A sliding capture needs every square in between to be empty at the time, and a hop needs
+//!   its hurdle to be occupied.
+//! - A sequential counter keeps each piece within the captures it has left.
+//!
+//! Only the squares that start out occupied ever hold a piece, so they are the only ones
+//! encoded.
+
+pub mod cdcl;
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+use web_time::Instant;
+
+use super::budget::Budget;
+use super::strategy::{SearchTask, Solver};
+use super::{Progress, SolveOutcome, SolveStats, SolverConfig, check_position, king_required};
+use crate::board::{Board, SIZE, STRIDE, Shape};
+use crate::piece::{Piece, PieceType};
+use crate::rules::{PawnDirection, Rules};
+use crate::step::{Step, square_name};
+use cdcl::{Answer, Cdcl};
+
+/// A formula in conjunctive normal form. Variables are numbered from 1 and a literal is a
+/// variable or its negation, as in DIMACS.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Cnf {
+    vars: usize,
+    clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn new_var(&mut self) -> i32 {
+        self.vars += 1;
+        self.vars as i32
+    }
+
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        self.clauses.push(clause.to_vec());
+    }
+
+    pub fn vars(&self) -> usize {
+        self.vars
+    }
+
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// At most `k` of `lits` are true, as a sequential counter: `k` extra variables per literal
+    /// count how many of the literals up to it are true.
+    pub fn at_most(&mut self, lits: &[i32], k: usize) {
+        let n = lits.len();
+        if k >= n {
+            return;
+        }
+        if k == 0 {
+            for &x in lits {
+                self.add_clause(&[-x]);
+            }
+            return;
+        }
+        let s: Vec<Vec<i32>> = (0..n - 1)
+            .map(|_| (0..k).map(|_| self.new_var()).collect())
+            .collect();
+        self.add_clause(&[-lits[0], s[0][0]]);
+        for &later in &s[0][1..] {
+            self.add_clause(&[-later]);
+        }
+        for i in 1..n - 1 {
+            self.add_clause(&[-lits[i], s[i][0]]);
+            self.add_clause(&[-s[i - 1][0], s[i][0]]);
+            for j in 1..k {
+                self.add_clause(&[-lits[i], -s[i - 1][j - 1], s[i][j]]);
+                self.add_clause(&[-s[i - 1][j], s[i][j]]);
+            }
+            self.add_clause(&[-lits[i], -s[i - 1][k - 1]]);
+        }
+        self.add_clause(&[-lits[n - 1], -s[n - 2][k - 1]]);
+    }
+
+    pub fn exactly_one(&mut self, lits: &[i32]) {
+        self.add_clause(lits);
+        self.at_most(lits, 1);
+    }
+
+    /// The formula in DIMACS format.
+    pub fn to_dimacs(&self) -> String {
+        let mut out = String::new();
+        self.write_dimacs(&mut out);
+        out
+    }
+
+    fn write_dimacs(&self, out: &mut String) {
+        writeln!(out, "p cnf {} {}", self.vars, self.clauses.len()).unwrap();
+        for clause in &self.clauses {
+            for lit in clause {
+                write!(out, "{lit} ").unwrap();
+            }
+            out.push_str("0\n");
+        }
+    }
+}
+
+/// A puzzle as a [`Cnf`], with the meaning of its capture variables.
+#[derive(Clone, Debug)]
+pub struct Encoding {
+    pub cnf: Cnf,
+    /// For every step, the capture variables and the capture each one stands for.
+    captures: Vec<Vec<(i32, Step)>>,
+    pieces: Vec<(usize, usize, Piece)>,
+    rows: usize,
+}
+
+impl Encoding {
+    /// Capture variables for every step, in order.
+    pub fn captures(&self) -> &[Vec<(i32, Step)>] {
+        &self.captures
+    }
+
+    /// The capture sequence a satisfying assignment stands for. `model[v]` is the value of
+    /// variable `v`; index 0 is unused.
+    pub fn decode(&self, model: &[bool]) -> Vec<Step> {
+        self.captures
+            .iter()
+            .filter_map(|step| {
+                step.iter()
+                    .find(|&&(var, _)| model[var as usize])
+                    .map(|&(_, step)| step)
+            })
+            .collect()
+    }
+
+    /// The formula in DIMACS format. Comment lines ahead of it list the pieces, with the
+    /// captures each has left, and name every capture variable so a model from another solver
+    /// can be read back:
+    ///
+    /// ```text
+    /// c piece <piece> <square> <captures left, or "unlimited">
+    /// c capture <variable> <step> <piece> <from> <to>
+    /// ```
+    pub fn to_dimacs(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "c Solo Chess puzzle, {} captures", self.captures.len()).unwrap();
+        for &(r, c, p) in &self.pieces {
+            let left = if p.is_unlimited() {
+                "unlimited".to_string()
+            } else {
+                p.move_left().to_string()
+            };
+            let square = square_name(r, c, self.rows);
+            writeln!(out, "c piece {:?} {square} {left}", p.piece_type).unwrap();
+        }
+        for (t, step) in self.captures.iter().enumerate() {
+            for &(var, capture) in step {
+                let ((fr, fc), (tr, tc)) = (capture.from, capture.to);
+                writeln!(
+                    out,
+                    "c capture {var} {} {:?} {} {}",
+                    t + 1,
+                    capture.piece_type,
+                    square_name(fr, fc, self.rows),
+                    square_name(tr, tc, self.rows)
+                )
+                .unwrap();
+            }
+        }
+        self.cnf.write_dimacs(&mut out);
+        out
+    }
+}
+
+/// A way for a piece to capture from one square onto another.
+struct Path {
+    to: usize,
+    /// Squares that have to be empty.
+    between: Vec<usize>,
+    /// Square that has to be occupied, for a hop.
+    hurdle: Option<usize>,
+}
+
+/// Poses `board` under `rules` as a formula that is satisfiable exactly when the puzzle can be
+/// solved. Unlike the search, it makes no exception for boards that are not valid puzzles.
+pub fn encode(board: &Board, rules: &Rules) -> Encoding {
+    let shape = board.shape();
+    let pieces: Vec<(usize, usize, Piece)> = board.pieces().collect();
+    let n = pieces.len();
+    let steps = n.saturating_sub(1);
+    let mut index = [None; SIZE];
+    for (k, &(r, c, _)) in pieces.iter().enumerate() {
+        index[r * STRIDE + c] = Some(k);
+    }
+
+    // Piece `i` captures at most `budget[i]` times, and from square `a` along `paths[i][a]`.
+    let budget: Vec<usize> = pieces
+        .iter()
+        .map(|(_, _, p)| {
+            if p.is_unlimited() {
+                steps
+            } else {
+                p.move_left().min(steps)
+            }
+        })
+        .collect();
+    let paths: Vec<Vec<Vec<Path>>> = pieces
+        .iter()
+        .map(|&(_, _, p)| {
+            pieces
+                .iter()
+                .map(|&(r, c, _)| {
+                    capture_paths(shape, p.piece_type, rules.pawn_direction, (r, c), &index)
+                })
+                .collect()
+        })
+        .collect();
+    // Fewest captures piece `i` needs to get to each square, whatever stands in the way.
+    let distance: Vec<Vec<usize>> = (0..n)
+        .map(|i| {
+            let mut distance = vec![usize::MAX; n];
+            distance[i] = 0;
+            let mut queue = VecDeque::from([i]);
+            while let Some(a) = queue.pop_front() {
+                for path in &paths[i][a] {
+                    if distance[path.to] == usize::MAX {
+                        distance[path.to] = distance[a] + 1;
+                        queue.push_back(path.to);
+                    }
+                }
+            }
+            distance
+        })
+        .collect();
+
+    let mut cnf = Cnf::new();
+    if n == 0 {
+        cnf.add_clause(&[]);
+    }
+
+    // `at[i][s][t]`, for squares piece `i` can have got to after `t` captures.
+    let at: Vec<Vec<Vec<Option<i32>>>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|s| {
+                    (0..=steps)
+                        .map(|t| (distance[i][s] <= t.min(budget[i])).then(|| cnf.new_var()))
+                        .collect()
+                })
+                .collect()
+        })
+        .collect();
+    for (i, at) in at.iter().enumerate() {
+        cnf.add_clause(&[at[i][0].unwrap()]);
+    }
+    // Pieces that may be on square `s` after `t` captures, with their variables.
+    let on = {
+        let at = &at;
+        move |s: usize, t: usize| (0..n).filter_map(move |j| at[j][s][t].map(|v| (j, v)))
+    };
+
+    // Squares that have to be empty, as `occupied(s, t)` implied by every piece on `s`.
+    let mut occupied = HashMap::new();
+    let mut occupied_var = |cnf: &mut Cnf, s: usize, t: usize| {
+        *occupied.entry((s, t)).or_insert_with(|| {
+            let var = cnf.new_var();
+            for (_, at) in on(s, t) {
+                cnf.add_clause(&[-at, var]);
+            }
+            var
+        })
+    };
+
+    let kings: Vec<usize> = (0..n).filter(|&i| pieces[i].2.is_king()).collect();
+    let mut captures = Vec::new();
+    // `moved[i][t]` is implied by every capture piece `i` makes at step `t`.
+    let mut moved: Vec<Vec<i32>> = vec![Vec::new(); n];
+    for t in 0..steps {
+        // Captures by piece, from and onto every square.
+        let mut by = vec![Vec::new(); n];
+        let mut from = vec![Vec::new(); n];
+        let mut onto = vec![Vec::new(); n];
+        let mut step = Vec::new();
+        for i in 0..n {
+            if budget[i] == 0 {
+                continue;
+            }
+            for a in (0..n).filter(|&a| distance[i][a] <= t.min(budget[i] - 1)) {
+                for path in &paths[i][a] {
+                    let b = path.to;
+                    let var = cnf.new_var();
+                    let (fr, fc, _) = pieces[a];
+                    let (tr, tc, _) = pieces[b];
+                    step.push((
+                        var,
+                        Step {
+                            from: (fr, fc),
+                            to: (tr, tc),
+                            piece_type: pieces[i].2.piece_type,
+                        },
+                    ));
+                    by[i].push(var);
+                    from[a].push((i, var));
+                    onto[b].push((i, var));
+
+                    cnf.add_clause(&[-var, at[i][a][t].unwrap()]);
+                    let mut victim = vec![-var];
+                    victim.extend(on(b, t).filter(|&(j, _)| j != i).map(|(_, v)| v));
+                    cnf.add_clause(&victim);
+                    if !rules.kings_capturable {
+                        for &king in kings.iter().filter(|&&king| king != i) {
+                            if let Some(king) = at[king][b][t] {
+                                cnf.add_clause(&[-var, -king]);
+                            }
+                        }
+                    }
+                    for &s in &path.between {
+                        let occupied = occupied_var(&mut cnf, s, t);
+                        cnf.add_clause(&[-var, -occupied]);
+                    }
+                    if let Some(h) = path.hurdle {
+                        let mut hurdle = vec![-var];
+                        hurdle.extend(on(h, t).map(|(_, v)| v));
+                        cnf.add_clause(&hurdle);
+                    }
+
+                    cnf.add_clause(&[-var, at[i][b][t + 1].unwrap()]);
+                    if let Some(left) = at[i][a][t + 1] {
+                        cnf.add_clause(&[-var, -left]);
+                    }
+                    for (_, taken) in on(b, t + 1).filter(|&(j, _)| j != i) {
+                        cnf.add_clause(&[-var, -taken]);
+                    }
+                }
+            }
+        }
+        let vars: Vec<i32> = step.iter().map(|&(var, _)| var).collect();
+        cnf.exactly_one(&vars);
+
+        // A piece only appears on a square by capturing onto it, and only leaves one by
+        // capturing from it or being captured.
+        for (j, at) in at.iter().enumerate() {
+            for (s, at) in at.iter().enumerate() {
+                if let Some(after) = at[t + 1] {
+                    let mut came = vec![-after];
+                    came.extend(at[t]);
+                    came.extend(onto[s].iter().filter(|&&(i, _)| i == j).map(|&(_, v)| v));
+                    cnf.add_clause(&came);
+                }
+                if let Some(before) = at[t] {
+                    let mut went = vec![-before];
+                    went.extend(at[t + 1]);
+                    went.extend(from[s].iter().filter(|&&(i, _)| i == j).map(|&(_, v)| v));
+                    went.extend(onto[s].iter().filter(|&&(i, _)| i != j).map(|&(_, v)| v));
+                    cnf.add_clause(&went);
+                }
+            }
+        }
+
+        for (i, vars) in by.iter().enumerate() {
+            if budget[i] < steps && !vars.is_empty() {
+                let var = cnf.new_var();
+                for &capture in vars {
+                    cnf.add_clause(&[-capture, var]);
+                }
+                moved[i].push(var);
+            }
+        }
+        captures.push(step);
+    }
+    for (i, moved) in moved.iter().enumerate() {
+        cnf.at_most(moved, budget[i]);
+    }
+
+    // Whatever is left after the last capture has to meet the King rule and the goal.
+    let king_required = king_required(board, rules);
+    for (i, &(_, _, p)) in pieces.iter().enumerate() {
+        if (king_required && !p.is_king())
+            || rules.goal.piece_type.is_some_and(|t| t != p.piece_type)
+        {
+            for v in at[i].iter().filter_map(|at| at[steps]) {
+                cnf.add_clause(&[-v]);
+            }
+        }
+    }
+    if let Some((r, c)) = rules.goal.square {
+        let target = shape
+            .contains(r, c)
+            .then(|| index[r * STRIDE + c])
+            .flatten();
+        let last: Vec<i32> = target
+            .map(|s| on(s, steps).map(|(_, v)| v).collect())
+            .unwrap_or_default();
+        cnf.add_clause(&last);
+    }
+
+    Encoding {
+        cnf,
+        captures,
+        pieces,
+        rows: shape.rows,
+    }
+}
+
+/// Every way a `piece_type` on `from` can capture onto another occupied square, by index in
+/// `index`.
+fn capture_paths(
+    shape: Shape,
+    piece_type: PieceType,
+    pawns: PawnDirection,
+    (r, c): (usize, usize),
+    index: &[Option<usize>; SIZE],
+) -> Vec<Path> {
+    let movement = piece_type.movement();
+    // Movement is written for pawns capturing toward row 0.
+    let facing = |(dr, dc): (i32, i32)| match pawns {
+        PawnDirection::Up => (dr, dc),
+        PawnDirection::Down => (-dr, dc),
+    };
+    // Squares along a line from `(r, c)`, nearest first.
+    let line = |(dr, dc): (i32, i32)| {
+        (1..).map_while(move |n| {
+            let (r, c) = (r as i32 + n * dr, c as i32 + n * dc);
+            (r >= 0 && c >= 0 && shape.contains(r as usize, c as usize))
+                .then(|| r as usize * STRIDE + c as usize)
+        })
+    };
+
+    let mut paths = Vec::new();
+    for &d in movement.leaps {
+        if let Some(to) = line(facing(d)).next().and_then(|sq| index[sq]) {
+            paths.push(Path {
+                to,
+                between: Vec::new(),
+                hurdle: None,
+            });
+        }
+    }
+    for &d in movement.rides {
+        let mut between = Vec::new();
+        for to in line(facing(d)).filter_map(|sq| index[sq]) {
+            paths.push(Path {
+                to,
+                between: between.clone(),
+                hurdle: None,
+            });
+            between.push(to);
+        }
+    }
+    for &d in movement.hops {
+        let mut between = Vec::new();
+        let mut previous = None;
+        for sq in line(facing(d)) {
+            // The hurdle has to be a square that can hold a piece.
+            if let (Some(to), Some(Some(hurdle))) = (index[sq], previous) {
+                paths.push(Path {
+                    to,
+                    between: between.clone(),
+                    hurdle: Some(hurdle),
+                });
+            }
+            if let Some(Some(s)) = previous {
+                between.push(s);
+            }
+            previous = Some(index[sq]);
+        }
+    }
+    paths
+}
+
+/// Solves the puzzle as a SAT problem with [`cdcl`]. Every decision the SAT solver makes
+/// counts as a node. The first slice only encodes the board, so a caller can yield or cancel
+/// before solving starts.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sat;
+
+impl Solver for Sat {
+    fn start(&self, board: &Board, config: &SolverConfig) -> Box<dyn SearchTask> {
+        Box::new(SatTask {
+            board: *board,
+            rules: config.rules,
+            solver: None,
+            budget: Budget::new(config),
+            stats: SolveStats::default(),
+            outcome: check_position(board, &config.rules),
+        })
+    }
+}
+
+struct SatTask {
+    board: Board,
+    rules: Rules,
+    /// Built by the first slice.
+    solver: Option<(Encoding, Cdcl)>,
+    budget: Budget,
+    stats: SolveStats,
+    outcome: Option<SolveOutcome>,
+}
+
+impl SatTask {
+    fn depth(&self) -> usize {
+        self.solver.as_ref().map_or(0, |(_, cdcl)| cdcl.level())
+    }
+}
+
+impl SearchTask for SatTask {
+    fn run(&mut self, slice: u64) -> Option<SolveOutcome> {
+        if self.outcome.is_none() {
+            let start = Instant::now();
+            let pause_at = self.budget.nodes().saturating_add(slice);
+            let budget = &mut self.budget;
+            let answer = match &mut self.solver {
+                Some((_, cdcl)) => cdcl.run(|| budget.nodes() < pause_at && budget.tick()),
+                None if budget.tick() => {
+                    let encoding = encode(&self.board, &self.rules);
+                    let cdcl = Cdcl::new(&encoding.cnf);
+                    self.solver = Some((encoding, cdcl));
+                    None
+                }
+                None => None,
+            };
+            self.outcome = match answer {
+                Some(Answer::Satisfiable(model)) => {
+                    let (encoding, _) = self.solver.as_ref().unwrap();
+                    Some(SolveOutcome::Solved(encoding.decode(&model)))
+                }
+                Some(Answer::Unsatisfiable) => Some(SolveOutcome::Unsolvable),
+                None if self.budget.exhausted() => Some(SolveOutcome::Aborted {
+                    nodes_searched: self.budget.nodes(),
+                }),
+                None => None,
+            };
+            self.stats.expanded = self.budget.nodes();
+            self.stats.max_depth = self.stats.max_depth.max(self.depth());
+            self.stats.elapsed += start.elapsed();
+        }
+        self.outcome.clone()
+    }
+
+    fn progress(&self) -> Progress {
+        Progress {
+            nodes: self.budget.nodes(),
+            depth: self.depth(),
+            dead_positions: 0,
+        }
+    }
+
+    fn stats(&self) -> SolveStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::rng::Rng;
+    use crate::rules::Goal;
+    use crate::solver::solve_all_with;
+
+    /// Every capture sequence a model of `board`'s encoding stands for, found by ruling out
+    /// each one in turn.
+    fn models(board: &Board, rules: &Rules) -> HashSet<Vec<Step>> {
+        let encoding = encode(board, rules);
+        let mut cnf = encoding.cnf.clone();
+        let mut found = HashSet::new();
+        while let Answer::Satisfiable(model) = cdcl::solve(&cnf) {
+            let taken: Vec<i32> = encoding
+                .captures()
+                .iter()
+                .flatten()
+                .filter(|&&(var, _)| model[var as usize])
+                .map(|&(var, _)| -var)
+                .collect();
+            cnf.add_clause(&taken);
+            assert!(found.insert(encoding.decode(&model)));
+        }
+        found
+    }
+
+    #[test]
+    fn models_are_the_solutions() {
+        let mut rng = Rng::new(5);
+        for case in 0..200 {
+            let shape = Shape::new(3 + rng.below(3), 3 + rng.below(3)).unwrap();
+            let rules = Rules {
+                capture_limit: *rng.pick(&Rules::CAPTURE_LIMITS),
+                king_must_survive: rng.below(2) == 0,
+                kings_capturable: rng.below(2) == 0,
+                pawn_direction: *rng.pick(&[PawnDirection::Up, PawnDirection::Down]),
+                goal: Goal {
+                    piece_type: (rng.below(3) == 0).then(|| *rng.pick(&PieceType::ALL)),
+                    square: (rng.below(3) == 0)
+                        .then(|| (rng.below(shape.rows), rng.below(shape.cols))),
+                },
+            };
+            let mut board = Board::with_shape(shape);
+            for _ in 0..2 + rng.below(3) {
+                let (r, c) = (rng.below(shape.rows), rng.below(shape.cols));
+                board.set_cell(r, c, rules.piece(*rng.pick(&PieceType::ALL)));
+            }
+            let config = SolverConfig {
+                rules,
+                ..SolverConfig::default()
+            };
+            let solutions: HashSet<Vec<Step>> = solve_all_with(&board, None, &config).collect();
+            assert_eq!(solutions, models(&board, &rules), "case {case}");
+        }
+    }
+
+    #[test]
+    fn goal_square_and_budgets() {
+        // Only the Queen with three captures left can take both Rooks and land on h8.
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::with_moves(PieceType::Queen, 3));
+        board.set_cell(0, 3, Piece::with_moves(PieceType::Rook, 0));
+        board.set_cell(0, 7, Piece::with_moves(PieceType::Rook, 0));
+        let mut rules = Rules::default();
+        assert_eq!(1, models(&board, &rules).len());
+        rules.goal.square = Some((0, 3));
+        assert!(models(&board, &rules).is_empty());
+
+        board.set_cell(0, 0, Piece::with_moves(PieceType::Queen, 1));
+        rules.goal.square = None;
+        assert!(models(&board, &rules).is_empty());
+    }
+
+    #[test]
+    fn dimacs_names_the_captures() {
+        let mut board = Board::new();
+        board.set_cell(7, 0, Piece::new(PieceType::Rook));
+        board.set_cell(7, 7, Piece::new(PieceType::Rook));
+        let encoding = encode(&board, &Rules::default());
+        let dimacs = encoding.to_dimacs();
+        let (comments, formula): (Vec<&str>, Vec<&str>) =
+            dimacs.lines().partition(|line| line.starts_with('c'));
+        assert_eq!(
+            vec![
+                "c Solo Chess puzzle, 1 captures",
+                "c piece Rook a1 2",
+                "c piece Rook h1 2",
+                "c capture 7 1 Rook a1 h1",
+                "c capture 8 1 Rook h1 a1",
+            ],
+            comments
+        );
+        assert_eq!(
+            format!(
+                "p cnf {} {}",
+                encoding.cnf.vars(),
+                encoding.cnf.clauses().len()
+            ),
+            formula[0]
+        );
+        assert_eq!(encoding.cnf.to_dimacs(), formula.join("\n") + "\n");
+        assert!(formula[1..].iter().all(|line| line.ends_with(" 0")));
+    }
+}
@@ -0,0 +1,459 @@
+//! A small conflict-driven clause learning SAT solver: two watched literals, first-UIP
+//! learning with backjumping, activity-based decisions with saved phases, and Luby restarts.
+//! Learnt clauses are never deleted, which keeps it simple at the cost of memory on long runs.
+
+use std::collections::BinaryHeap;
+
+use super::Cnf;
+
+/// Conflicts between restarts, scaled by the Luby sequence.
+const RESTART_BASE: u64 = 100;
+const ACTIVITY_DECAY: f64 = 0.95;
+
+/// What [`Cdcl::run`] found.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Answer {
+    /// `model[v]` is the value of variable `v`; index 0 is unused.
+    Satisfiable(Vec<bool>),
+    Unsatisfiable,
+}
+
+/// Solves `cnf` to the end.
+pub fn solve(cnf: &Cnf) -> Answer {
+    Cdcl::new(cnf)
+        .run(|| true)
+        .expect("a solver that may always go on finishes")
+}
+
+/// Variable `v` as literal `2v` and its negation as `2v + 1`, with variables from 0.
+type Lit = usize;
+
+fn lit(dimacs: i32) -> Lit {
+    (dimacs.unsigned_abs() as usize - 1) * 2 + (dimacs < 0) as usize
+}
+
+/// Value of `l` under `values`, `None` while unassigned.
+fn value(values: &[Option<bool>], l: Lit) -> Option<bool> {
+    values[l / 2].map(|v| v != (l % 2 == 1))
+}
+
+/// A solver that runs until it is told to stop, and can be resumed.
+pub struct Cdcl {
+    clauses: Vec<Vec<Lit>>,
+    /// Clauses whose first or second literal is the index, to look at once it turns false.
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    /// Decision level each variable was assigned at.
+    levels: Vec<usize>,
+    /// Clause that forced each variable, `None` for decisions.
+    reasons: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    /// Start of every decision level in `trail`.
+    trail_limits: Vec<usize>,
+    /// Next literal on the trail to propagate.
+    head: usize,
+    activity: Vec<f64>,
+    increment: f64,
+    /// Unassigned variables by activity, with stale entries skipped when popped.
+    order: BinaryHeap<(u64, usize)>,
+    /// Value each variable had last, tried first when deciding it.
+    phases: Vec<bool>,
+    seen: Vec<bool>,
+    conflicts: u64,
+    restarts: u32,
+    next_restart: u64,
+    /// Set once a conflict arises with no decisions to undo.
+    unsatisfiable: bool,
+}
+
+impl Cdcl {
+    pub fn new(cnf: &Cnf) -> Self {
+        let vars = cnf.vars();
+        let mut solver = Self {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * vars],
+            values: vec![None; vars],
+            levels: vec![0; vars],
+            reasons: vec![None; vars],
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            head: 0,
+            activity: vec![0.0; vars],
+            increment: 1.0,
+            order: (0..vars).map(|v| (0, v)).collect(),
+            phases: vec![false; vars],
+            seen: vec![false; vars],
+            conflicts: 0,
+            restarts: 0,
+            next_restart: RESTART_BASE,
+            unsatisfiable: false,
+        };
+        for clause in cnf.clauses() {
+            solver.add_clause(clause);
+        }
+        solver
+    }
+
+    fn add_clause(&mut self, clause: &[i32]) {
+        let mut clause: Vec<Lit> = clause.iter().map(|&l| lit(l)).collect();
+        clause.sort_unstable();
+        clause.dedup();
+        if clause.windows(2).any(|w| w[0] / 2 == w[1] / 2) {
+            return;
+        }
+        match clause[..] {
+            [] => self.unsatisfiable = true,
+            [l] => match value(&self.values, l) {
+                None => self.assign(l, None),
+                Some(false) => self.unsatisfiable = true,
+                Some(true) => {}
+            },
+            _ => {
+                self.watch(self.clauses.len(), &clause);
+                self.clauses.push(clause);
+            }
+        }
+    }
+
+    fn watch(&mut self, index: usize, clause: &[Lit]) {
+        self.watches[clause[0]].push(index);
+        self.watches[clause[1]].push(index);
+    }
+
+    /// Current decision level.
+    pub fn level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    /// Searches until the answer is known, or until `go_on` returns `false` before a decision.
+    /// Returns `None` in that case; calling `run` again carries on from there.
+    pub fn run(&mut self, mut go_on: impl FnMut() -> bool) -> Option<Answer> {
+        loop {
+            if self.unsatisfiable {
+                return Some(Answer::Unsatisfiable);
+            }
+            if let Some(conflict) = self.propagate() {
+                self.conflicts += 1;
+                if self.level() == 0 {
+                    self.unsatisfiable = true;
+                    continue;
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                match learnt[..] {
+                    [l] => self.assign(l, None),
+                    _ => {
+                        let index = self.clauses.len();
+                        self.watch(index, &learnt);
+                        self.assign(learnt[0], Some(index));
+                        self.clauses.push(learnt);
+                    }
+                }
+                self.increment /= ACTIVITY_DECAY;
+                continue;
+            }
+
+            if self.conflicts >= self.next_restart {
+                self.restarts += 1;
+                self.next_restart = self.conflicts + RESTART_BASE * luby(self.restarts);
+                self.backtrack(0);
+            }
+            let Some(var) = self.pick() else {
+                let model = std::iter::once(false)
+                    .chain(self.values.iter().map(|v| v.unwrap()))
+                    .collect();
+                return Some(Answer::Satisfiable(model));
+            };
+            if !go_on() {
+                self.order.push((self.activity[var].to_bits(), var));
+                return None;
+            }
+            self.trail_limits.push(self.trail.len());
+            self.assign(2 * var + !self.phases[var] as usize, None);
+        }
+    }
+
+    fn assign(&mut self, l: Lit, reason: Option<usize>) {
+        let var = l / 2;
+        self.values[var] = Some(l.is_multiple_of(2));
+        self.levels[var] = self.level();
+        self.reasons[var] = reason;
+        self.trail.push(l);
+    }
+
+    /// Unassigned variable with the highest activity.
+    fn pick(&mut self) -> Option<usize> {
+        while let Some((activity, var)) = self.order.pop() {
+            if self.values[var].is_none() && activity == self.activity[var].to_bits() {
+                return Some(var);
+            }
+        }
+        None
+    }
+
+    /// Assigns every literal the clauses force. Returns a clause left with every literal
+    /// false, if there is one.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.head < self.trail.len() {
+            let false_lit = self.trail[self.head] ^ 1;
+            self.head += 1;
+            let mut watching = std::mem::take(&mut self.watches[false_lit]);
+            let mut kept = 0;
+            let mut conflict = None;
+            let mut i = 0;
+            while i < watching.len() {
+                let index = watching[i];
+                i += 1;
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if value(&self.values, first) == Some(true) {
+                    watching[kept] = index;
+                    kept += 1;
+                    continue;
+                }
+                if let Some(k) =
+                    (2..clause.len()).find(|&k| value(&self.values, clause[k]) != Some(false))
+                {
+                    clause.swap(1, k);
+                    self.watches[clause[1]].push(index);
+                    continue;
+                }
+                watching[kept] = index;
+                kept += 1;
+                if value(&self.values, first) == Some(false) {
+                    conflict = Some(index);
+                    break;
+                }
+                self.assign(first, Some(index));
+            }
+            while i < watching.len() {
+                watching[kept] = watching[i];
+                kept += 1;
+                i += 1;
+            }
+            watching.truncate(kept);
+            self.watches[false_lit] = watching;
+            if conflict.is_some() {
+                self.head = self.trail.len();
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Learns a clause from `conflict` that asserts one literal once the search backjumps to
+    /// the level returned with it.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut learnt = vec![0];
+        let mut open = 0;
+        let mut reason = conflict;
+        let mut skip_first = false;
+        let mut position = self.trail.len();
+        let asserting = loop {
+            for k in skip_first as usize..self.clauses[reason].len() {
+                let l = self.clauses[reason][k];
+                let var = l / 2;
+                if self.seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+                self.seen[var] = true;
+                self.bump(var);
+                if self.levels[var] == self.level() {
+                    open += 1;
+                } else {
+                    learnt.push(l);
+                }
+            }
+            loop {
+                position -= 1;
+                if self.seen[self.trail[position] / 2] {
+                    break;
+                }
+            }
+            let l = self.trail[position];
+            self.seen[l / 2] = false;
+            open -= 1;
+            if open == 0 {
+                break l;
+            }
+            reason = self.reasons[l / 2].expect("only the first UIP can be a decision");
+            skip_first = true;
+        };
+        learnt[0] = asserting ^ 1;
+        for &l in &learnt[1..] {
+            self.seen[l / 2] = false;
+        }
+
+        // The literal assigned last among the rest is watched next to the asserting one.
+        let mut level = 0;
+        if let Some((k, _)) = learnt
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by_key(|&(_, &l)| self.levels[l / 2])
+        {
+            learnt.swap(1, k);
+            level = self.levels[learnt[1] / 2];
+        }
+        (learnt, level)
+    }
+
+    fn bump(&mut self, var: usize) {
+        self.activity[var] += self.increment;
+        if self.activity[var] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+            self.increment *= 1e-100;
+            self.order = (0..self.values.len())
+                .filter(|&v| self.values[v].is_none())
+                .map(|v| (self.activity[v].to_bits(), v))
+                .collect();
+        }
+        if self.values[var].is_none() {
+            self.order.push((self.activity[var].to_bits(), var));
+        }
+    }
+
+    /// Undoes every assignment above decision level `level`.
+    fn backtrack(&mut self, level: usize) {
+        if self.level() <= level {
+            return;
+        }
+        let start = self.trail_limits[level];
+        for l in self.trail.drain(start..) {
+            let var = l / 2;
+            self.phases[var] = l.is_multiple_of(2);
+            self.values[var] = None;
+            self.reasons[var] = None;
+            self.order.push((self.activity[var].to_bits(), var));
+        }
+        self.trail_limits.truncate(level);
+        self.head = self.trail.len();
+    }
+}
+
+/// The `i`th term of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, ..., from 1.
+fn luby(i: u32) -> u64 {
+    let mut i = i as u64;
+    loop {
+        let k = 64 - i.leading_zeros() as u64;
+        if i == (1 << k) - 1 {
+            return 1 << (k - 1);
+        }
+        i -= (1 << (k - 1)) - 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cnf(vars: usize, clauses: &[&[i32]]) -> Cnf {
+        let mut cnf = Cnf::new();
+        for _ in 0..vars {
+            cnf.new_var();
+        }
+        for clause in clauses {
+            cnf.add_clause(clause);
+        }
+        cnf
+    }
+
+    fn satisfies(cnf: &Cnf, model: &[bool]) -> bool {
+        cnf.clauses().iter().all(|clause| {
+            clause
+                .iter()
+                .any(|&l| model[l.unsigned_abs() as usize] == (l > 0))
+        })
+    }
+
+    /// Every one of `pigeons` pigeons gets a hole, and no hole holds two.
+    fn pigeonhole(pigeons: usize, holes: usize) -> Cnf {
+        let mut cnf = Cnf::new();
+        let sits: Vec<Vec<i32>> = (0..pigeons)
+            .map(|_| (0..holes).map(|_| cnf.new_var()).collect())
+            .collect();
+        for pigeon in &sits {
+            cnf.add_clause(pigeon);
+        }
+        for hole in 0..holes {
+            let column: Vec<i32> = sits.iter().map(|pigeon| pigeon[hole]).collect();
+            cnf.at_most(&column, 1);
+        }
+        cnf
+    }
+
+    #[test]
+    fn small_formulas() {
+        let sat = cnf(3, &[&[1, 2], &[-1, 3], &[-2, -3], &[-3, 1]]);
+        match solve(&sat) {
+            Answer::Satisfiable(model) => assert!(satisfies(&sat, &model)),
+            Answer::Unsatisfiable => panic!("satisfiable"),
+        }
+        let unsat = cnf(2, &[&[1, 2], &[-1, 2], &[1, -2], &[-1, -2]]);
+        assert_eq!(Answer::Unsatisfiable, solve(&unsat));
+        assert_eq!(Answer::Unsatisfiable, solve(&cnf(1, &[&[1], &[-1]])));
+        assert_eq!(Answer::Unsatisfiable, solve(&cnf(0, &[&[]])));
+        assert_eq!(Answer::Satisfiable(vec![false]), solve(&cnf(0, &[])));
+    }
+
+    #[test]
+    fn pigeons_and_holes() {
+        for holes in 1..6 {
+            let fits = pigeonhole(holes, holes);
+            match solve(&fits) {
+                Answer::Satisfiable(model) => assert!(satisfies(&fits, &model)),
+                Answer::Unsatisfiable => panic!("{holes} pigeons fit in {holes} holes"),
+            }
+            assert_eq!(Answer::Unsatisfiable, solve(&pigeonhole(holes + 1, holes)));
+        }
+    }
+
+    #[test]
+    fn cardinality() {
+        // Exactly `k` of 6 variables are true for every `k` the constraints allow.
+        for k in 0..=6 {
+            let mut cnf = Cnf::new();
+            let vars: Vec<i32> = (0..6).map(|_| cnf.new_var()).collect();
+            cnf.at_most(&vars, k);
+            for &v in &vars[..k] {
+                cnf.add_clause(&[v]);
+            }
+            assert!(matches!(solve(&cnf), Answer::Satisfiable(_)), "{k}");
+            if k < 6 {
+                cnf.add_clause(&[vars[k]]);
+                assert_eq!(Answer::Unsatisfiable, solve(&cnf), "{k}");
+            }
+        }
+    }
+
+    #[test]
+    fn stops_when_told_and_resumes() {
+        let cnf = pigeonhole(6, 5);
+        let mut solver = Cdcl::new(&cnf);
+        assert_eq!(None, solver.run(|| false));
+        let mut pauses = 0;
+        let answer = loop {
+            let mut slice = 10;
+            if let Some(answer) = solver.run(|| {
+                slice -= 1;
+                slice > 0
+            }) {
+                break answer;
+            }
+            pauses += 1;
+        };
+        assert_eq!(Answer::Unsatisfiable, answer);
+        assert!(pauses > 0);
+    }
+
+    #[test]
+    fn luby_sequence() {
+        let terms: Vec<u64> = (1..16).map(luby).collect();
+        assert_eq!(vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8], terms);
+    }
+}
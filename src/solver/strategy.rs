@@ -0,0 +1,648 @@
+//! Interchangeable ways of searching for the first solution, so they can be compared on the
+//! same positions.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use web_time::Instant;
+
+use super::budget::Budget;
+use super::sat::Sat;
+use super::table::{DeadSet, TranspositionTable};
+use super::tablebase::{self, Tablebase};
+use super::{
+    Progress, Resume, Solutions, SolveOutcome, SolveStats, SolveTask, SolverConfig, Verdict,
+    check_position, king_required, list_capture_pairs_into, settle, solve_all_with,
+};
+use crate::board::Board;
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::step::Step;
+
+/// A way of searching for a solution.
+pub trait Solver {
+    /// Starts a search of `board` that runs a slice at a time.
+    fn start(&self, board: &Board, config: &SolverConfig) -> Box<dyn SearchTask>;
+
+    /// Searches `board` to the end.
+    fn solve(&self, board: &Board, config: &SolverConfig) -> (SolveOutcome, SolveStats) {
+        let mut task = self.start(board, config);
+        loop {
+            if let Some(outcome) = task.run(u64::MAX) {
+                return (outcome, task.stats());
+            }
+        }
+    }
+}
+
+/// A search started by [`Solver::start`], like [`SolveTask`].
+pub trait SearchTask {
+    /// Searches at most `slice` more nodes. Returns the outcome once it is known.
+    fn run(&mut self, slice: u64) -> Option<SolveOutcome>;
+    fn progress(&self) -> Progress;
+    fn stats(&self) -> SolveStats;
+}
+
+impl SearchTask for SolveTask {
+    fn run(&mut self, slice: u64) -> Option<SolveOutcome> {
+        SolveTask::run(self, slice)
+    }
+
+    fn progress(&self) -> Progress {
+        SolveTask::progress(self)
+    }
+
+    fn stats(&self) -> SolveStats {
+        SolveTask::stats(self)
+    }
+}
+
+/// Every strategy in the crate, for picking one by name.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Strategy {
+    #[default]
+    DepthFirst,
+    IterativeDeepening,
+    BestFirst,
+    RandomRestarts,
+    Sat,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 5] = [
+        Strategy::DepthFirst,
+        Strategy::IterativeDeepening,
+        Strategy::BestFirst,
+        Strategy::RandomRestarts,
+        Strategy::Sat,
+    ];
+
+    /// Name used on the command line, such as "best-first".
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::DepthFirst => "depth-first",
+            Strategy::IterativeDeepening => "iterative-deepening",
+            Strategy::BestFirst => "best-first",
+            Strategy::RandomRestarts => "random-restarts",
+            Strategy::Sat => "sat",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Strategy::DepthFirst => "Depth-first",
+            Strategy::IterativeDeepening => "Iterative deepening",
+            Strategy::BestFirst => "Best-first",
+            Strategy::RandomRestarts => "Random restarts",
+            Strategy::Sat => "SAT (CDCL)",
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A name [`Strategy::from_str`] does not know.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UnknownStrategy(pub String);
+
+impl fmt::Display for UnknownStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = Strategy::ALL.iter().map(|s| s.name()).collect();
+        write!(
+            f,
+            "unknown strategy \"{}\", expected one of {}",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = UnknownStrategy;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Strategy::ALL
+            .into_iter()
+            .find(|strategy| strategy.name() == s)
+            .ok_or_else(|| UnknownStrategy(s.to_string()))
+    }
+}
+
+impl Solver for Strategy {
+    fn start(&self, board: &Board, config: &SolverConfig) -> Box<dyn SearchTask> {
+        match self {
+            Strategy::DepthFirst => DepthFirst.start(board, config),
+            Strategy::IterativeDeepening => IterativeDeepening.start(board, config),
+            Strategy::BestFirst => BestFirst::default().start(board, config),
+            Strategy::RandomRestarts => RandomRestarts::default().start(board, config),
+            Strategy::Sat => Sat.start(board, config),
+        }
+    }
+}
+
+/// The search behind [`super::solve_with`]: depth-first, trying the captures of the pieces with
+/// the fewest options first and remembering positions found to have no solution.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DepthFirst;
+
+impl Solver for DepthFirst {
+    fn start(&self, board: &Board, config: &SolverConfig) -> Box<dyn SearchTask> {
+        Box::new(SolveTask::new(board, config))
+    }
+}
+
+/// Depth-first search that first only follows the move order, then allows one capture off it,
+/// then two, and so on. Every solution is exactly one capture per piece but the last deep, so
+/// the search deepens in how far it strays from the move order rather than in captures.
+///
+/// Positions proven dead carry over from one pass to the next.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IterativeDeepening;
+
+impl Solver for IterativeDeepening {
+    fn start(&self, board: &Board, config: &SolverConfig) -> Box<dyn SearchTask> {
+        let mut solutions = solve_all_with(board, Some(1), config);
+        solutions.discrepancy_limit = Some(0);
+        Box::new(RestartingTask {
+            solutions,
+            outcome: check_position(board, &config.rules),
+            board: *board,
+            config: config.clone(),
+            restart_at: None,
+            restarts: Restarts::Deepen,
+        })
+    }
+}
+
+/// Depth-first search with ties in the move order broken at random, abandoned and restarted
+/// with a new seed after a number of nodes that doubles with every restart. A restart gets
+/// another chance at the early captures a single search would be stuck behind.
+///
+/// Positions proven dead carry over from one restart to the next, and the growing limit makes
+/// the search complete.
+#[derive(Clone, Copy, Debug)]
+pub struct RandomRestarts {
+    pub seed: u64,
+    /// Nodes searched before the first restart.
+    pub first_restart: u64,
+}
+
+impl Default for RandomRestarts {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            first_restart: 1000,
+        }
+    }
+}
+
+impl Solver for RandomRestarts {
+    fn start(&self, board: &Board, config: &SolverConfig) -> Box<dyn SearchTask> {
+        let mut solutions = solve_all_with(board, Some(1), config);
+        solutions.shuffle = Some(Rng::new(self.seed));
+        Box::new(RestartingTask {
+            solutions,
+            outcome: check_position(board, &config.rules),
+            board: *board,
+            config: config.clone(),
+            restart_at: Some(self.first_restart.max(1)),
+            restarts: Restarts::Random {
+                seed: self.seed,
+                limit: self.first_restart.max(1),
+            },
+        })
+    }
+}
+
+/// How a [`RestartingTask`] sets up its next pass.
+enum Restarts {
+    /// Allow one more capture off the move order, once a pass left some untried.
+    Deepen,
+    /// Shuffle with the next seed and double the node limit, once the limit is reached.
+    Random { seed: u64, limit: u64 },
+}
+
+/// Runs passes of the depth-first search, sharing the dead set, budget and stats between them.
+struct RestartingTask {
+    solutions: Solutions,
+    outcome: Option<SolveOutcome>,
+    board: Board,
+    config: SolverConfig,
+    /// Node count at which the pass is abandoned.
+    restart_at: Option<u64>,
+    restarts: Restarts,
+}
+
+impl RestartingTask {
+    fn restart(&mut self) {
+        let nodes = self.solutions.nodes_searched();
+        let placeholder =
+            DeadSet::Local(TranspositionTable::with_capacity_mb(0, &Rules::default()));
+        let mut next = Solutions::new(
+            &self.board,
+            self.solutions.king_required,
+            Some(1),
+            &self.config,
+            placeholder,
+        );
+        std::mem::swap(&mut next.dead, &mut self.solutions.dead);
+        std::mem::swap(&mut next.budget, &mut self.solutions.budget);
+        next.stats = self.solutions.stats;
+        match &mut self.restarts {
+            Restarts::Deepen => {
+                next.discrepancy_limit = self.solutions.discrepancy_limit.map(|limit| limit + 1);
+            }
+            Restarts::Random { seed, limit } => {
+                *seed = seed.wrapping_add(1);
+                *limit = limit.saturating_mul(2);
+                next.shuffle = Some(Rng::new(*seed));
+                self.restart_at = Some(nodes.saturating_add(*limit));
+            }
+        }
+        self.solutions = next;
+    }
+}
+
+impl SearchTask for RestartingTask {
+    fn run(&mut self, slice: u64) -> Option<SolveOutcome> {
+        let pause_at = self.solutions.nodes_searched().saturating_add(slice);
+        while self.outcome.is_none() {
+            let nodes = self.solutions.nodes_searched();
+            let until = self.restart_at.map_or(pause_at, |at| at.min(pause_at));
+            if nodes >= pause_at {
+                return None;
+            }
+            self.outcome = match self.solutions.resume(until.saturating_sub(nodes)) {
+                Resume::Found(steps) => Some(SolveOutcome::Solved(steps)),
+                Resume::Finished if self.solutions.aborted() => Some(SolveOutcome::Aborted {
+                    nodes_searched: self.solutions.nodes_searched(),
+                }),
+                Resume::Finished if !self.solutions.cut => Some(SolveOutcome::Unsolvable),
+                Resume::Finished => {
+                    self.restart();
+                    None
+                }
+                Resume::Paused => {
+                    if self
+                        .restart_at
+                        .is_some_and(|at| self.solutions.nodes_searched() >= at)
+                    {
+                        self.restart();
+                    }
+                    None
+                }
+            };
+        }
+        self.outcome.clone()
+    }
+
+    fn progress(&self) -> Progress {
+        self.solutions.progress()
+    }
+
+    fn stats(&self) -> SolveStats {
+        self.solutions.stats()
+    }
+}
+
+/// Expands whichever open position ranks best, anywhere in the search tree. Positions closer to
+/// the end rank first, then those `priority` scores higher, then the newest. Every position
+/// reached is kept, so the search is aborted once they would outgrow
+/// [`SolverConfig::table_mb`].
+#[derive(Clone, Copy, Debug)]
+pub struct BestFirst {
+    /// Scores a position; higher is searched first. A learned model can be plugged in here.
+    pub priority: fn(&Board) -> usize,
+}
+
+impl Default for BestFirst {
+    fn default() -> Self {
+        Self {
+            priority: spare_captures,
+        }
+    }
+}
+
+/// Captures left over after clearing the board, so positions with more room for error come
+/// first.
+pub fn spare_captures(board: &Board) -> usize {
+    board
+        .sum_move_left()
+        .saturating_sub(board.count_pieces().saturating_sub(1))
+}
+
+impl Solver for BestFirst {
+    fn start(&self, board: &Board, config: &SolverConfig) -> Box<dyn SearchTask> {
+        let mut task = BestFirstTask {
+            root: *board,
+            rules: config.rules,
+            king_required: king_required(board, &config.rules),
+            tablebase: config
+                .tablebase
+                .clone()
+                .filter(|tablebase| tablebase.covers(board, &config.rules)),
+            priority: self.priority,
+            budget: Budget::new(config),
+            stats: SolveStats::default(),
+            nodes: Vec::new(),
+            open: BinaryHeap::new(),
+            seen: HashSet::new(),
+            capacity: (config.table_mb << 20) / POSITION_BYTES,
+            depth: 0,
+            outcome: check_position(board, &config.rules),
+        };
+        if task.outcome.is_none() {
+            task.outcome = task.add(board, None);
+        }
+        Box::new(task)
+    }
+}
+
+/// Memory a position reached by [`BestFirst`] takes at most: its node, its place in the queue,
+/// and its key with the set's spare room.
+const POSITION_BYTES: usize =
+    size_of::<Node>() + size_of::<(Reverse<usize>, usize, usize)>() + 2 * size_of::<u64>();
+
+/// A position reached by [`BestFirst`], as the capture that led to it from its parent.
+struct Node {
+    parent: Option<usize>,
+    step: Step,
+}
+
+struct BestFirstTask {
+    root: Board,
+    rules: Rules,
+    king_required: bool,
+    tablebase: Option<Arc<Tablebase>>,
+    priority: fn(&Board) -> usize,
+    budget: Budget,
+    stats: SolveStats,
+    nodes: Vec<Node>,
+    /// Open positions by rank, as indices into `nodes`. The root is `usize::MAX`.
+    open: BinaryHeap<(Reverse<usize>, usize, usize)>,
+    /// Keys of every position reached so far.
+    seen: HashSet<u64>,
+    /// Most positions `seen` may hold.
+    capacity: usize,
+    /// Captures made on the way to the position expanded last.
+    depth: usize,
+    outcome: Option<SolveOutcome>,
+}
+
+impl BestFirstTask {
+    /// Judges `board`, reached from `parent` or the root, and queues it if it stays open.
+    /// Returns the outcome if this ends the search.
+    fn add(&mut self, board: &Board, parent: Option<(usize, Step)>) -> Option<SolveOutcome> {
+        if !self.budget.tick() {
+            return Some(SolveOutcome::Aborted {
+                nodes_searched: self.budget.nodes(),
+            });
+        }
+        if self.seen.contains(&board.key()) {
+            self.stats.memo_hits += 1;
+            return None;
+        }
+        if self.seen.len() >= self.capacity {
+            return Some(SolveOutcome::Aborted {
+                nodes_searched: self.budget.nodes(),
+            });
+        }
+        self.seen.insert(board.key());
+        match settle(board, &self.rules, self.king_required) {
+            Some(Verdict::Solved) => {
+                let mut steps = parent.map_or_else(Vec::new, |(index, _)| self.path(index));
+                steps.extend(parent.map(|(_, step)| step));
+                return Some(SolveOutcome::Solved(steps));
+            }
+            Some(verdict) => {
+                self.stats.count_prune(verdict);
+                return None;
+            }
+            None => {}
+        }
+        if let Some(tablebase) = &self.tablebase
+            && board.count_pieces() == tablebase::MAX_PIECES
+            && !tablebase.solvable(board)
+        {
+            self.stats.tablebase_prunes += 1;
+            return None;
+        }
+
+        let index = match parent {
+            Some((parent, step)) => {
+                self.nodes.push(Node {
+                    parent: (parent != usize::MAX).then_some(parent),
+                    step,
+                });
+                self.nodes.len() - 1
+            }
+            None => usize::MAX,
+        };
+        let rank = (Reverse(board.count_pieces()), (self.priority)(board), index);
+        self.open.push(rank);
+        None
+    }
+
+    /// The captures leading from the root to `nodes[index]`.
+    fn path(&self, index: usize) -> Vec<Step> {
+        let mut steps = Vec::new();
+        let mut next = (index != usize::MAX).then_some(index);
+        while let Some(index) = next {
+            steps.push(self.nodes[index].step);
+            next = self.nodes[index].parent;
+        }
+        steps.reverse();
+        steps
+    }
+
+    /// Expands the best open position. Returns the outcome if this ends the search.
+    fn expand(&mut self) -> Option<SolveOutcome> {
+        let Some((_, _, index)) = self.open.pop() else {
+            return Some(SolveOutcome::Unsolvable);
+        };
+        let mut board = self.root;
+        let path = self.path(index);
+        for step in &path {
+            play(&mut board, *step);
+        }
+        self.depth = path.len();
+        self.stats.expanded += 1;
+        self.stats.max_depth = self.stats.max_depth.max(self.depth);
+
+        let mut moves = Vec::new();
+        list_capture_pairs_into(&board, &self.rules, &mut moves);
+        for step in moves {
+            let mut after = board;
+            play(&mut after, step);
+            if let Some(outcome) = self.add(&after, Some((index, step))) {
+                return Some(outcome);
+            }
+        }
+        None
+    }
+}
+
+impl SearchTask for BestFirstTask {
+    fn run(&mut self, slice: u64) -> Option<SolveOutcome> {
+        let start = Instant::now();
+        let pause_at = self.budget.nodes().saturating_add(slice);
+        while self.outcome.is_none() && self.budget.nodes() < pause_at {
+            self.outcome = self.expand();
+        }
+        self.stats.elapsed += start.elapsed();
+        self.outcome.clone()
+    }
+
+    fn progress(&self) -> Progress {
+        // Nothing is proven dead here; positions are only ever skipped as already seen.
+        Progress {
+            nodes: self.budget.nodes(),
+            depth: self.depth,
+            dead_positions: 0,
+        }
+    }
+
+    fn stats(&self) -> SolveStats {
+        self.stats
+    }
+}
+
+fn play(board: &mut Board, step: Step) {
+    let ((fr, fc), (tr, tc)) = (step.from, step.to);
+    board.move_piece(fr, fc, tr, tc);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::generate;
+    use crate::piece::{Piece, PieceType};
+    use crate::solver::{InvalidPosition, solve_with, validate::validate};
+
+    const MIX: &[PieceType] = &[
+        PieceType::King,
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Pawn,
+    ];
+
+    fn board(pieces: &[(usize, usize, PieceType)]) -> Board {
+        let mut board = Board::new();
+        for &(r, c, piece_type) in pieces {
+            board.set_cell(r, c, Piece::new(piece_type));
+        }
+        board
+    }
+
+    #[test]
+    fn strategies_agree() {
+        let config = SolverConfig::default();
+        let unsolvable = board(&[
+            (0, 0, PieceType::Rook),
+            (0, 7, PieceType::Rook),
+            (7, 0, PieceType::Knight),
+            (4, 4, PieceType::Bishop),
+        ]);
+        let mut boards: Vec<Board> = (0..8)
+            .map(|seed| generate(8, MIX, &config.rules, seed))
+            .collect();
+        boards.push(unsolvable);
+        for board in &boards {
+            let expected = solve_with(board, &config) == SolveOutcome::Unsolvable;
+            for strategy in Strategy::ALL {
+                match strategy.solve(board, &config).0 {
+                    SolveOutcome::Solved(steps) => {
+                        assert!(!expected, "{strategy}");
+                        assert_eq!(Ok(()), validate(board, &steps, &config.rules), "{strategy}");
+                    }
+                    outcome => assert_eq!(SolveOutcome::Unsolvable, outcome, "{strategy}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn positions_that_need_no_search() {
+        let config = SolverConfig::default();
+        for strategy in Strategy::ALL {
+            assert_eq!(
+                SolveOutcome::InvalidPosition(InvalidPosition::EmptyBoard),
+                strategy.solve(&Board::new(), &config).0
+            );
+            assert_eq!(
+                SolveOutcome::AlreadySolved,
+                strategy
+                    .solve(&board(&[(3, 3, PieceType::Rook)]), &config)
+                    .0
+            );
+        }
+    }
+
+    #[test]
+    fn budgets_abort_every_strategy() {
+        let config = SolverConfig {
+            max_nodes: Some(5),
+            ..SolverConfig::default()
+        };
+        let board = generate(10, MIX, &config.rules, 1);
+        for strategy in Strategy::ALL {
+            assert!(
+                matches!(
+                    strategy.solve(&board, &config).0,
+                    SolveOutcome::Aborted { .. }
+                ),
+                "{strategy}"
+            );
+        }
+    }
+
+    #[test]
+    fn best_first_stops_when_memory_runs_out() {
+        let config = SolverConfig {
+            table_mb: 0,
+            ..SolverConfig::default()
+        };
+        let board = generate(10, MIX, &config.rules, 1);
+        assert!(matches!(
+            BestFirst::default().solve(&board, &config).0,
+            SolveOutcome::Aborted { .. }
+        ));
+    }
+
+    #[test]
+    fn tasks_run_in_slices() {
+        let config = SolverConfig::default();
+        let board = generate(9, MIX, &config.rules, 4);
+        for strategy in Strategy::ALL {
+            let mut task = strategy.start(&board, &config);
+            let mut slices = 0;
+            let outcome = loop {
+                if let Some(outcome) = task.run(1) {
+                    break outcome;
+                }
+                slices += 1;
+            };
+            assert!(slices > 0, "{strategy}");
+            assert!(matches!(outcome, SolveOutcome::Solved(_)), "{strategy}");
+            assert_eq!(outcome, strategy.solve(&board, &config).0, "{strategy}");
+            assert!(task.stats().expanded > 0);
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for strategy in Strategy::ALL {
+            assert_eq!(Ok(strategy), strategy.name().parse());
+        }
+        assert!("breadth-first".parse::<Strategy>().is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use std::mem::size_of;
+#[cfg(feature = "parallel")]
+use std::sync::{
+    Arc,
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use crate::board::Board;
+use crate::rules::Rules;
+
+/// The set of positions known to have no solution, as seen by one search.
+pub enum DeadSet {
+    Local(TranspositionTable<()>),
+    /// Shared with searches running on other threads.
+    #[cfg(feature = "parallel")]
+    Shared(Arc<SharedTable>),
+}
+
+impl DeadSet {
+    pub fn contains(&self, board: &Board) -> bool {
+        match self {
+            DeadSet::Local(table) => table.contains(board),
+            #[cfg(feature = "parallel")]
+            DeadSet::Shared(table) => table.contains(board),
+        }
+    }
+
+    pub fn insert(&mut self, board: &Board) {
+        match self {
+            DeadSet::Local(table) => table.insert(board, ()),
+            #[cfg(feature = "parallel")]
+            DeadSet::Shared(table) => table.insert(board),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            DeadSet::Local(table) => table.len(),
+            #[cfg(feature = "parallel")]
+            DeadSet::Shared(table) => table.len(),
+        }
+    }
+}
+
+/// Fixed-size hash table keyed by a board's canonical Zobrist key, so a board and its mirror
+/// image share an entry unless a goal square tells them apart. Values must therefore not depend
+/// on which side is which; the table holds verdicts and counts, never steps.
+///
+/// Every bucket has two slots. One keeps the entry with the most pieces, since it stands for
+/// the largest subtree; the other always takes the newest entry. Losing an entry only costs a
+/// re-search, so the table never grows past the size it was built with.
+pub struct TranspositionTable<V> {
+    buckets: Vec<Bucket<V>>,
+    len: usize,
+    mirrors: bool,
+}
+
+#[derive(Clone, Copy)]
+struct Entry<V> {
+    key: u64,
+    pieces: u16,
+    value: V,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket<V> {
+    deep: Option<Entry<V>>,
+    recent: Option<Entry<V>>,
+}
+
+impl<V: Copy> TranspositionTable<V> {
+    pub fn with_capacity_mb(mb: usize, rules: &Rules) -> Self {
+        Self::with_buckets(
+            (mb << 20) / size_of::<Bucket<V>>(),
+            rules.goal.is_mirror_symmetric(),
+        )
+    }
+
+    /// Rounds `n` down to a power of two, with at least one bucket.
+    fn with_buckets(n: usize, mirrors: bool) -> Self {
+        let n = if n == 0 { 1 } else { 1 << n.ilog2() };
+        Self {
+            buckets: vec![
+                Bucket {
+                    deep: None,
+                    recent: None,
+                };
+                n
+            ],
+            len: 0,
+            mirrors,
+        }
+    }
+
+    pub fn get(&self, board: &Board) -> Option<V> {
+        let key = key(board, self.mirrors);
+        let bucket = &self.buckets[self.index(key)];
+        [bucket.deep, bucket.recent]
+            .into_iter()
+            .flatten()
+            .find(|e| e.key == key)
+            .map(|e| e.value)
+    }
+
+    pub fn contains(&self, board: &Board) -> bool {
+        self.get(board).is_some()
+    }
+
+    pub fn insert(&mut self, board: &Board, value: V) {
+        let entry = Entry {
+            key: key(board, self.mirrors),
+            pieces: board.count_pieces() as u16,
+            value,
+        };
+        let i = self.index(entry.key);
+        let bucket = &mut self.buckets[i];
+        let before = bucket.len();
+
+        match bucket.deep {
+            _ if bucket.recent.is_some_and(|e| e.key == entry.key) => bucket.recent = Some(entry),
+            Some(deep) if deep.key == entry.key => bucket.deep = Some(entry),
+            Some(deep) if deep.pieces > entry.pieces => bucket.recent = Some(entry),
+            deep => {
+                bucket.recent = deep.or(bucket.recent);
+                bucket.deep = Some(entry);
+            }
+        }
+
+        self.len = self.len + bucket.len() - before;
+    }
+
+    /// Number of entries currently held.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn index(&self, key: u64) -> usize {
+        key as usize & (self.buckets.len() - 1)
+    }
+}
+
+impl<V> Bucket<V> {
+    fn len(&self) -> usize {
+        self.deep.is_some() as usize + self.recent.is_some() as usize
+    }
+}
+
+/// Lock-free set of Zobrist keys, canonical where the goal allows, that any number of threads can read and write at once.
+///
+/// Each slot holds a single key and is always overwritten. A zero slot is empty, which only
+/// clashes with the key of the empty board, and that board is never searched.
+#[cfg(feature = "parallel")]
+pub struct SharedTable {
+    slots: Vec<AtomicU64>,
+    len: AtomicUsize,
+    mirrors: bool,
+}
+
+#[cfg(feature = "parallel")]
+impl SharedTable {
+    pub fn with_capacity_mb(mb: usize, rules: &Rules) -> Self {
+        let n = (mb << 20) / size_of::<AtomicU64>();
+        let n = if n == 0 { 1 } else { 1 << n.ilog2() };
+        Self {
+            slots: (0..n).map(|_| AtomicU64::new(0)).collect(),
+            len: AtomicUsize::new(0),
+            mirrors: rules.goal.is_mirror_symmetric(),
+        }
+    }
+
+    pub fn contains(&self, board: &Board) -> bool {
+        let key = key(board, self.mirrors);
+        self.slot(key).load(Ordering::Relaxed) == key
+    }
+
+    pub fn insert(&self, board: &Board) {
+        let key = key(board, self.mirrors);
+        if self.slot(key).swap(key, Ordering::Relaxed) == 0 {
+            self.len.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of slots filled so far.
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    fn slot(&self, key: u64) -> &AtomicU64 {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+}
+
+/// The key `board` is stored under, shared with its mirror image when `mirrors` is set.
+fn key(board: &Board, mirrors: bool) -> u64 {
+    if mirrors {
+        board.canonical_key()
+    } else {
+        board.key()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::piece::{Piece, PieceType};
+
+    fn board_with(squares: &[(usize, usize)]) -> Board {
+        let mut board = Board::new();
+        for &(r, c) in squares {
+            board.set_cell(r, c, Piece::new(PieceType::Pawn));
+        }
+        board
+    }
+
+    #[test]
+    fn single_bucket_keeps_deepest_and_newest() {
+        let mut table = TranspositionTable::with_buckets(1, true);
+        let deep = board_with(&[(0, 0), (0, 1), (0, 2)]);
+        let a = board_with(&[(1, 0), (1, 1)]);
+        let b = board_with(&[(2, 0), (2, 1)]);
+
+        table.insert(&deep, 3);
+        table.insert(&a, 1);
+        table.insert(&b, 2);
+
+        assert_eq!(Some(3), table.get(&deep));
+        assert_eq!(None, table.get(&a));
+        assert_eq!(Some(2), table.get(&b));
+        assert_eq!(2, table.len());
+    }
+
+    #[test]
+    fn mirror_image_hits_the_same_entry() {
+        let mut table = TranspositionTable::with_capacity_mb(1, &Rules::default());
+        let board = board_with(&[(0, 0), (1, 2), (5, 3)]);
+        table.insert(&board, 7);
+        assert_eq!(Some(7), table.get(&board.mirrored()));
+        assert_eq!(1, table.len());
+
+        table.insert(&board.mirrored(), 8);
+        assert_eq!(Some(8), table.get(&board));
+        assert_eq!(1, table.len());
+    }
+
+    #[test]
+    fn goal_square_keeps_mirror_images_apart() {
+        let mut rules = Rules::default();
+        rules.goal.square = Some((1, 3));
+        let mut table = TranspositionTable::with_capacity_mb(1, &rules);
+        let board = board_with(&[(0, 0), (1, 2), (5, 3)]);
+        table.insert(&board, 7);
+        assert_eq!(None, table.get(&board.mirrored()));
+        assert_eq!(Some(7), table.get(&board));
+    }
+
+    #[test]
+    fn capacity_is_bounded() {
+        let table = TranspositionTable::<()>::with_capacity_mb(1, &Rules::default());
+        assert!(table.buckets.len() * size_of::<Bucket<()>>() <= 1 << 20);
+        assert!(table.buckets.len().is_power_of_two());
+    }
+}
@@ -0,0 +1,520 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::sync::{Mutex, MutexGuard};
+
+use super::{Verdict, king_required, list_capture_pairs_into, settle};
+use crate::board::{Board, Shape};
+use crate::piece::{Piece, PieceType};
+use crate::rules::{Goal, PawnDirection, Rules};
+
+/// Positions with at most this many pieces go in a [`Tablebase`].
+pub const MAX_PIECES: usize = 4;
+
+/// A [`Tablebase`] stops taking new positions once it holds this many, a few MB.
+pub const MAX_ENTRIES: usize = 1 << 18;
+
+const MAGIC: &[u8; 4] = b"SCTB";
+const VERSION: u8 = 1;
+/// Stands for "any" in the goal fields of the file header.
+const NONE: u8 = u8::MAX;
+
+/// Whether positions with up to [`MAX_PIECES`] pieces can be solved, for one board shape and
+/// one set of rules. Every budget combination is covered, since a piece's remaining captures
+/// are part of the position, but a full table would hold about a billion solvable positions
+/// for the standard pieces alone. So this is a partial cache, not a complete table: a position
+/// missing from it is searched to the end, and every small position met on the way is added,
+/// up to [`MAX_ENTRIES`]. Past that, see [`Tablebase::is_full`], positions the table does not
+/// hold are searched each time, which with so few pieces stays cheap. Share one through
+/// [`super::SolverConfig::tablebase`] to keep what one search learns for the next.
+///
+/// [`Tablebase::to_bytes`] saves the table in a compact binary format, so a table warmed up
+/// ahead of time can ship with the app. It holds only the positions met while warming it up.
+pub struct Tablebase {
+    shape: Shape,
+    rules: Rules,
+    entries: Mutex<Entries>,
+    capacity: usize,
+}
+
+type Entries = HashMap<u64, bool, BuildHasherDefault<KeyHasher>>;
+
+/// Position keys are already random, so they serve as their own hash.
+#[derive(Default)]
+struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, _: &[u8]) {
+        unreachable!("only u64 keys are hashed")
+    }
+
+    fn write_u64(&mut self, key: u64) {
+        self.0 = key;
+    }
+}
+
+/// Why bytes could not be read as a [`Tablebase`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TablebaseError {
+    /// The bytes do not start like a tablebase file.
+    NotATablebase,
+    UnsupportedVersion(u8),
+    /// The file was written with different position keys, so its entries mean nothing here.
+    KeysChanged,
+    /// The header names a board shape or rules this crate does not know.
+    BadHeader,
+    /// The file ends early, or an entry does not decode.
+    Malformed,
+}
+
+impl fmt::Display for TablebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TablebaseError::NotATablebase => write!(f, "not a tablebase file"),
+            TablebaseError::UnsupportedVersion(v) => {
+                write!(f, "tablebase format version {v} is not supported")
+            }
+            TablebaseError::KeysChanged => {
+                write!(f, "the tablebase was written with different position keys")
+            }
+            TablebaseError::BadHeader => write!(f, "the tablebase header is not valid"),
+            TablebaseError::Malformed => write!(f, "the tablebase file is damaged"),
+        }
+    }
+}
+
+impl fmt::Debug for Tablebase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tablebase")
+            .field("shape", &self.shape)
+            .field("rules", &self.rules)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Tablebase {
+    /// An empty table for positions on a `shape` board under `rules`.
+    pub fn new(shape: Shape, rules: &Rules) -> Self {
+        Self::with_capacity(shape, rules, MAX_ENTRIES)
+    }
+
+    fn with_capacity(shape: Shape, rules: &Rules, capacity: usize) -> Self {
+        Self {
+            shape,
+            rules: Self::game(rules),
+            entries: Mutex::new(Entries::default()),
+            capacity,
+        }
+    }
+
+    /// Whether the table's verdicts hold for `board` under `rules`.
+    pub fn covers(&self, board: &Board, rules: &Rules) -> bool {
+        board.shape() == self.shape && Self::game(rules) == self.rules
+    }
+
+    /// Number of positions whose verdict is known.
+    pub fn len(&self) -> usize {
+        self.entries().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the table has stopped taking new positions.
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.capacity
+    }
+
+    /// Whether `board`, which has at most [`MAX_PIECES`] pieces, can be solved. Computes and
+    /// records the verdict if the table does not have it yet.
+    pub fn solvable(&self, board: &Board) -> bool {
+        debug_assert!(board.count_pieces() <= MAX_PIECES);
+        let king_required = king_required(board, &self.rules);
+        let mut board = *board;
+        self.search(&mut board, king_required)
+    }
+
+    /// Decides `board` from its captures. Positions that [`settle`] decides are not
+    /// recorded: they are cheap to decide again, and a King lost on the way would be judged
+    /// differently on its own.
+    fn search(&self, board: &mut Board, king_required: bool) -> bool {
+        if let Some(verdict) = settle(board, &self.rules, king_required) {
+            return verdict == Verdict::Solved;
+        }
+        let key = self.key(board);
+        if let Some(&solvable) = self.entries().get(&key) {
+            return solvable;
+        }
+
+        let mut moves = Vec::new();
+        list_capture_pairs_into(board, &self.rules, &mut moves);
+        let solvable = moves.into_iter().any(|step| {
+            let ((fr, fc), (tr, tc)) = (step.from, step.to);
+            let (from_piece, to_piece) = (board.get_cell(fr, fc), board.get_cell(tr, tc));
+            board.move_piece(fr, fc, tr, tc);
+            let solvable = self.search(board, king_required);
+            board.set_cell(fr, fc, from_piece.unwrap());
+            board.set_cell(tr, tc, to_piece.unwrap());
+            solvable
+        });
+        let mut entries = self.entries();
+        if entries.len() < self.capacity {
+            entries.insert(key, solvable);
+        }
+        solvable
+    }
+
+    /// A goal square tells the two sides of the board apart, so mirror images only share an
+    /// entry without one.
+    fn key(&self, board: &Board) -> u64 {
+        if self.rules.goal.is_mirror_symmetric() {
+            board.canonical_key()
+        } else {
+            board.key()
+        }
+    }
+
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The rules that decide a position. The capture limit only sets how many captures new
+    /// pieces get, and those are part of the position.
+    fn game(rules: &Rules) -> Rules {
+        Rules {
+            capture_limit: None,
+            ..*rules
+        }
+    }
+
+    /// Saves the table. The format, all integers little-endian:
+    ///
+    /// - `SCTB`, then the format version as one byte.
+    /// - The key of a lone King on the first square, as a `u64`, to catch files written with
+    ///   different position keys.
+    /// - Rows and columns, one byte each.
+    /// - Rule flags as one byte: King must survive, Kings capturable, pawns capture downwards.
+    /// - The goal's piece type, row and column, one byte each, `0xff` for any.
+    /// - The solvable positions, then the ones without a solution. Each list is a `u32` count
+    ///   followed by its keys in ascending order, each stored as the LEB128 varint of its
+    ///   difference from the previous key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let rules = &self.rules;
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&key_check().to_le_bytes());
+        bytes.extend_from_slice(&[self.shape.rows as u8, self.shape.cols as u8]);
+        bytes.push(
+            rules.king_must_survive as u8
+                | (rules.kings_capturable as u8) << 1
+                | ((rules.pawn_direction == PawnDirection::Down) as u8) << 2,
+        );
+        let (r, c) = rules
+            .goal
+            .square
+            .map_or((NONE, NONE), |(r, c)| (r as u8, c as u8));
+        bytes.extend_from_slice(&[rules.goal.piece_type.map_or(NONE, |t| t as u8), r, c]);
+
+        let entries = self.entries();
+        for verdict in [true, false] {
+            let mut keys: Vec<u64> = entries
+                .iter()
+                .filter(|&(_, &solvable)| solvable == verdict)
+                .map(|(&key, _)| key)
+                .collect();
+            keys.sort_unstable();
+            bytes.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+            let mut previous = 0;
+            for key in keys {
+                write_varint(&mut bytes, key - previous);
+                previous = key;
+            }
+        }
+        bytes
+    }
+
+    /// Reads a table saved by [`Tablebase::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TablebaseError> {
+        let Some(mut reader) = bytes.strip_prefix(MAGIC).map(Reader) else {
+            return Err(TablebaseError::NotATablebase);
+        };
+        match reader.byte()? {
+            VERSION => {}
+            v => return Err(TablebaseError::UnsupportedVersion(v)),
+        }
+        if reader.u64()? != key_check() {
+            return Err(TablebaseError::KeysChanged);
+        }
+
+        let [rows, cols, flags, piece, r, c] = *reader.take(6)? else {
+            unreachable!()
+        };
+        let shape = Shape::new(rows as usize, cols as usize).ok_or(TablebaseError::BadHeader)?;
+        let piece_type = match piece {
+            NONE => None,
+            t => Some(
+                *PieceType::ALL
+                    .get(t as usize)
+                    .ok_or(TablebaseError::BadHeader)?,
+            ),
+        };
+        let square = match (r, c) {
+            (NONE, NONE) => None,
+            (r, c) if shape.contains(r as usize, c as usize) => Some((r as usize, c as usize)),
+            _ => return Err(TablebaseError::BadHeader),
+        };
+        let rules = Rules {
+            capture_limit: None,
+            king_must_survive: flags & 1 != 0,
+            kings_capturable: flags & 2 != 0,
+            pawn_direction: if flags & 4 != 0 {
+                PawnDirection::Down
+            } else {
+                PawnDirection::Up
+            },
+            goal: Goal { piece_type, square },
+        };
+
+        let tablebase = Self::new(shape, &rules);
+        let mut entries = tablebase.entries();
+        for verdict in [true, false] {
+            let mut key = 0u64;
+            for _ in 0..reader.u32()? {
+                key = key.wrapping_add(reader.varint()?);
+                if entries.len() < tablebase.capacity {
+                    entries.insert(key, verdict);
+                }
+            }
+        }
+        drop(entries);
+        Ok(tablebase)
+    }
+}
+
+/// Key of a lone King on the first square, which changes whenever position keys do.
+fn key_check() -> u64 {
+    let mut board = Board::new();
+    board.set_cell(0, 0, Piece::new(PieceType::King));
+    board.key()
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        bytes.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], TablebaseError> {
+        if self.0.len() < n {
+            return Err(TablebaseError::Malformed);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, TablebaseError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, TablebaseError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, TablebaseError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn varint(&mut self) -> Result<u64, TablebaseError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            n |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(TablebaseError::Malformed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generator::generate;
+    use crate::solver::{SolveOutcome, SolverConfig, solve_with, solve_with_stats};
+    use std::sync::Arc;
+
+    const MIX: &[PieceType] = &[
+        PieceType::King,
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+        PieceType::Pawn,
+    ];
+
+    /// Small positions, some solvable and some not: generated puzzles, and the same puzzles
+    /// with one piece spent or moved.
+    fn small_positions(rules: &Rules) -> Vec<Board> {
+        let mut boards = Vec::new();
+        for seed in 0..30 {
+            let board = generate(2 + seed as usize % 3, MIX, rules, seed);
+            let (r, c, p) = board.pieces().last().unwrap();
+            let mut spent = board;
+            spent.set_cell(r, c, Piece::with_moves(p.piece_type, 0));
+            let mut moved = board;
+            moved.clear_cell(r, c);
+            moved.set_cell(7 - r, c, p);
+            boards.extend([board, spent, moved]);
+        }
+        boards
+    }
+
+    #[test]
+    fn agrees_with_the_search() {
+        for rules in [
+            Rules::default(),
+            Rules {
+                kings_capturable: true,
+                king_must_survive: false,
+                pawn_direction: PawnDirection::Down,
+                goal: Goal {
+                    piece_type: None,
+                    square: Some((3, 2)),
+                },
+                ..Rules::default()
+            },
+        ] {
+            let tablebase = Tablebase::new(Shape::STANDARD, &rules);
+            let config = SolverConfig {
+                rules,
+                ..SolverConfig::default()
+            };
+            for board in small_positions(&rules) {
+                let solved = matches!(
+                    solve_with(&board, &config),
+                    SolveOutcome::Solved(_) | SolveOutcome::AlreadySolved
+                );
+                assert_eq!(solved, tablebase.solvable(&board));
+            }
+            assert!(!tablebase.is_empty());
+        }
+    }
+
+    #[test]
+    fn stops_growing_when_full() {
+        let rules = Rules::default();
+        let full = Tablebase::with_capacity(Shape::STANDARD, &rules, 3);
+        let unbounded = Tablebase::new(Shape::STANDARD, &rules);
+        for board in small_positions(&rules) {
+            assert_eq!(unbounded.solvable(&board), full.solvable(&board));
+        }
+        assert!(unbounded.len() > 3 && !unbounded.is_full());
+        assert_eq!(3, full.len());
+        assert!(full.is_full());
+    }
+
+    #[test]
+    fn solver_keeps_what_it_learns() {
+        let rules = Rules::default();
+        let board = generate(9, MIX, &rules, 2);
+        let tablebase = Arc::new(Tablebase::new(Shape::STANDARD, &rules));
+        let config = SolverConfig {
+            tablebase: Some(tablebase.clone()),
+            ..SolverConfig::default()
+        };
+
+        let (outcome, stats) = solve_with_stats(&board, &config);
+        assert_eq!(solve_with(&board, &SolverConfig::default()), outcome);
+        assert!(stats.tablebase_prunes > 0);
+        let learned = tablebase.len();
+        solve_with(&board, &config);
+        assert_eq!(learned, tablebase.len());
+
+        // Other rules get nothing from it.
+        let other = SolverConfig {
+            rules: Rules {
+                kings_capturable: true,
+                ..rules
+            },
+            ..config
+        };
+        assert_eq!(0, solve_with_stats(&board, &other).1.tablebase_prunes);
+    }
+
+    #[test]
+    fn shipped_table_loads() {
+        let tablebase = Tablebase::from_bytes(include_bytes!("../../assets/tablebase.bin"))
+            .expect("rebuild it with `cargo run --release --example build_tablebase`");
+        let board = generate(4, MIX, &Rules::default(), 0);
+        assert!(tablebase.covers(&board, &Rules::default()));
+        assert!(!tablebase.is_empty());
+        assert!(tablebase.solvable(&board));
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let rules = Rules {
+            capture_limit: Some(3),
+            pawn_direction: PawnDirection::Down,
+            goal: Goal {
+                piece_type: Some(PieceType::Rook),
+                square: Some((4, 1)),
+            },
+            ..Rules::default()
+        };
+        let shape = Shape::new(6, 9).unwrap();
+        let tablebase = Tablebase::new(shape, &rules);
+        let positions: Vec<Board> = small_positions(&Rules::default())
+            .into_iter()
+            .map(|board| board.resized(shape))
+            .collect();
+        let verdicts: Vec<bool> = positions.iter().map(|b| tablebase.solvable(b)).collect();
+
+        let bytes = tablebase.to_bytes();
+        let read = Tablebase::from_bytes(&bytes).unwrap();
+        assert_eq!(tablebase.len(), read.len());
+        assert!(read.covers(&positions[0], &rules));
+        assert_eq!(tablebase.to_bytes(), read.to_bytes());
+        assert_eq!(
+            verdicts,
+            positions
+                .iter()
+                .map(|b| read.solvable(b))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            Some(TablebaseError::NotATablebase),
+            Tablebase::from_bytes(b"PNG").err()
+        );
+        assert_eq!(
+            Some(TablebaseError::Malformed),
+            Tablebase::from_bytes(&bytes[..bytes.len() - 1]).err()
+        );
+        let mut newer = bytes.clone();
+        newer[4] = VERSION + 1;
+        assert_eq!(
+            Some(TablebaseError::UnsupportedVersion(VERSION + 1)),
+            Tablebase::from_bytes(&newer).err()
+        );
+        let mut rekeyed = bytes;
+        rekeyed[5] ^= 1;
+        assert_eq!(
+            Some(TablebaseError::KeysChanged),
+            Tablebase::from_bytes(&rekeyed).err()
+        );
+    }
+}
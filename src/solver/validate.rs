@@ -0,0 +1,272 @@
+use std::fmt;
+
+use crate::bitboard::{attacks, reach};
+use crate::board::{Board, STRIDE};
+use crate::piece::PieceType;
+use crate::rules::Rules;
+use crate::step::Step;
+
+/// Why a capture sequence does not solve a position. `index` counts steps from 0.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StepError {
+    /// A square of the step lies off the board.
+    OffBoard { index: usize },
+    /// There is no piece on the step's starting square.
+    NoPiece { index: usize },
+    /// The piece on the starting square is not the one the step names.
+    WrongPieceType {
+        index: usize,
+        expected: PieceType,
+        found: PieceType,
+    },
+    /// The target square is empty, so the step is not a capture.
+    NotACapture { index: usize },
+    /// The piece does not move that way, even on an empty board.
+    IllegalMove { index: usize },
+    /// The piece moves that way, but something stands in between.
+    Blocked { index: usize },
+    /// The target is a King, which the rules do not allow capturing.
+    TargetIsKing { index: usize },
+    /// The piece has no captures left.
+    OutOfMoves { index: usize },
+    /// Every step is legal, but more than one piece is left, or none was there to begin with.
+    PiecesRemain { count: usize },
+    /// One piece is left, but the King had to be the last piece.
+    KingNotLast,
+    /// One piece is left, but it does not meet the goal.
+    GoalNotMet,
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepError::OffBoard { index } => write!(f, "step {} leaves the board", index + 1),
+            StepError::NoPiece { index } => {
+                write!(f, "step {} starts from an empty square", index + 1)
+            }
+            StepError::WrongPieceType {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "step {} moves a {expected:?}, but the piece there is a {found:?}",
+                index + 1
+            ),
+            StepError::NotACapture { index } => {
+                write!(f, "step {} does not capture anything", index + 1)
+            }
+            StepError::IllegalMove { index } => {
+                write!(f, "step {} is not a move that piece can make", index + 1)
+            }
+            StepError::Blocked { index } => write!(f, "step {} is blocked", index + 1),
+            StepError::TargetIsKing { index } => {
+                write!(f, "step {} captures a King", index + 1)
+            }
+            StepError::OutOfMoves { index } => {
+                write!(f, "step {} moves a piece with no captures left", index + 1)
+            }
+            StepError::PiecesRemain { count } => write!(f, "{count} pieces are left"),
+            StepError::KingNotLast => write!(f, "the last piece is not the King"),
+            StepError::GoalNotMet => write!(f, "the last piece does not meet the goal"),
+        }
+    }
+}
+
+/// Checks that `steps` solve `board` under `rules`, replaying them one at a time. Returns the
+/// first thing wrong with the sequence.
+pub fn validate(board: &Board, steps: &[Step], rules: &Rules) -> Result<(), StepError> {
+    let shape = board.shape();
+    let king_required = rules.king_must_survive && board.has_king();
+    let mut board = *board;
+
+    for (index, step) in steps.iter().enumerate() {
+        let ((fr, fc), (tr, tc)) = (step.from, step.to);
+        if !shape.contains(fr, fc) || !shape.contains(tr, tc) {
+            return Err(StepError::OffBoard { index });
+        }
+        let piece = board.get_cell(fr, fc).ok_or(StepError::NoPiece { index })?;
+        if piece.piece_type != step.piece_type {
+            return Err(StepError::WrongPieceType {
+                index,
+                expected: step.piece_type,
+                found: piece.piece_type,
+            });
+        }
+        let target = board
+            .get_cell(tr, tc)
+            .ok_or(StepError::NotACapture { index })?;
+
+        let (from, to) = (fr * STRIDE + fc, tr * STRIDE + tc);
+        let pawns = rules.pawn_direction;
+        if !reach(shape, piece.piece_type, from, 1, pawns).contains(to) {
+            return Err(StepError::IllegalMove { index });
+        }
+        if !attacks(shape, piece.piece_type, from, board.occupied(), pawns).contains(to) {
+            return Err(StepError::Blocked { index });
+        }
+        if target.is_king() && !rules.kings_capturable {
+            return Err(StepError::TargetIsKing { index });
+        }
+        if piece.move_left() == 0 {
+            return Err(StepError::OutOfMoves { index });
+        }
+
+        board.move_piece(fr, fc, tr, tc);
+    }
+
+    let count = board.count_pieces();
+    if count > 1 {
+        return Err(StepError::PiecesRemain { count });
+    }
+    let Some((r, c, last)) = board.pieces().next() else {
+        return Err(StepError::PiecesRemain { count });
+    };
+    if king_required && !last.is_king() {
+        return Err(StepError::KingNotLast);
+    }
+    if !rules.goal.is_met_by(r, c, last) {
+        return Err(StepError::GoalNotMet);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::piece::Piece;
+    use crate::rules::Goal;
+
+    fn step(fr: usize, fc: usize, tr: usize, tc: usize, piece_type: PieceType) -> Step {
+        Step {
+            from: (fr, fc),
+            to: (tr, tc),
+            piece_type,
+        }
+    }
+
+    #[test]
+    fn accepts_what_the_solver_finds() {
+        let mut board = Board::new();
+        for (r, c, piece_type) in [
+            (3, 0, PieceType::Bishop),
+            (1, 1, PieceType::Knight),
+            (2, 3, PieceType::Queen),
+            (3, 2, PieceType::Knight),
+            (3, 5, PieceType::Knight),
+            (4, 2, PieceType::Knight),
+            (5, 5, PieceType::Bishop),
+            (6, 1, PieceType::Rook),
+        ] {
+            board.set_cell(r, c, Piece::new(piece_type));
+        }
+        let rules = Rules::default();
+        for steps in super::super::solve_all(&board, Some(20)) {
+            assert_eq!(Ok(()), validate(&board, &steps, &rules));
+        }
+    }
+
+    #[test]
+    fn reports_the_first_bad_step() {
+        let rules = Rules::default();
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::new(PieceType::Rook));
+        board.set_cell(0, 3, Piece::new(PieceType::Bishop));
+        board.set_cell(0, 7, Piece::new(PieceType::Knight));
+        board.set_cell(4, 4, Piece::new(PieceType::King));
+
+        let check = |steps: &[Step]| validate(&board, steps, &rules);
+        assert_eq!(
+            Err(StepError::OffBoard { index: 0 }),
+            check(&[step(0, 0, 0, 8, PieceType::Rook)])
+        );
+        assert_eq!(
+            Err(StepError::NoPiece { index: 0 }),
+            check(&[step(1, 0, 0, 0, PieceType::Rook)])
+        );
+        assert_eq!(
+            Err(StepError::WrongPieceType {
+                index: 0,
+                expected: PieceType::Queen,
+                found: PieceType::Rook
+            }),
+            check(&[step(0, 0, 0, 3, PieceType::Queen)])
+        );
+        assert_eq!(
+            Err(StepError::NotACapture { index: 0 }),
+            check(&[step(0, 0, 1, 0, PieceType::Rook)])
+        );
+        assert_eq!(
+            Err(StepError::IllegalMove { index: 0 }),
+            check(&[step(0, 0, 4, 4, PieceType::Rook)])
+        );
+        assert_eq!(
+            Err(StepError::Blocked { index: 0 }),
+            check(&[step(0, 0, 0, 7, PieceType::Rook)])
+        );
+        assert_eq!(
+            Err(StepError::PiecesRemain { count: 3 }),
+            check(&[step(0, 0, 0, 3, PieceType::Rook)])
+        );
+        assert_eq!(
+            Err(StepError::NoPiece { index: 1 }),
+            check(&[
+                step(0, 0, 0, 3, PieceType::Rook),
+                step(0, 0, 0, 3, PieceType::Rook)
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_what_is_wrong_with_the_end() {
+        let rules = Rules::default();
+        let mut board = Board::new();
+        board.set_cell(0, 0, Piece::new(PieceType::King));
+        board.set_cell(0, 1, Piece::new(PieceType::Rook));
+        board.set_cell(1, 2, Piece::with_moves(PieceType::Bishop, 0));
+
+        assert_eq!(
+            Err(StepError::TargetIsKing { index: 0 }),
+            validate(&board, &[step(0, 1, 0, 0, PieceType::Rook)], &rules)
+        );
+        assert_eq!(
+            Err(StepError::OutOfMoves { index: 0 }),
+            validate(&board, &[step(1, 2, 0, 1, PieceType::Bishop)], &rules)
+        );
+        assert_eq!(
+            Err(StepError::PiecesRemain { count: 2 }),
+            validate(&board, &[step(0, 0, 0, 1, PieceType::King)], &rules)
+        );
+
+        let solution = [
+            step(0, 0, 0, 1, PieceType::King),
+            step(0, 1, 1, 2, PieceType::King),
+        ];
+        assert_eq!(Ok(()), validate(&board, &solution, &rules));
+        let goal = Rules {
+            goal: Goal {
+                piece_type: None,
+                square: Some((0, 0)),
+            },
+            ..rules
+        };
+        assert_eq!(
+            Err(StepError::GoalNotMet),
+            validate(&board, &solution, &goal)
+        );
+
+        let capturable = Rules {
+            kings_capturable: true,
+            ..rules
+        };
+        assert_eq!(
+            Err(StepError::PiecesRemain { count: 2 }),
+            validate(&board, &[step(0, 1, 0, 0, PieceType::Rook)], &capturable)
+        );
+        board.clear_cell(1, 2);
+        assert_eq!(
+            Err(StepError::KingNotLast),
+            validate(&board, &[step(0, 1, 0, 0, PieceType::Rook)], &capturable)
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::board::Shape;
 use crate::piece::PieceType;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -6,3 +7,21 @@ pub struct Step {
     pub to: (usize, usize),
     pub piece_type: PieceType,
 }
+
+impl Step {
+    /// The same capture on the board reflected left to right, see [`crate::board::Board::mirrored`].
+    pub fn mirrored(self, shape: Shape) -> Step {
+        let flip = |(r, c): (usize, usize)| (r, shape.cols - 1 - c);
+        Step {
+            from: flip(self.from),
+            to: flip(self.to),
+            ..self
+        }
+    }
+}
+
+/// Algebraic name of square `(r, c)` on a board with `rows` ranks, such as "e4". Row 0 is the
+/// last rank.
+pub fn square_name(r: usize, c: usize, rows: usize) -> String {
+    format!("{}{}", (c as u8 + b'a') as char, rows - r)
+}
@@ -1,5 +1,11 @@
+pub mod background;
+pub mod board_size;
 pub mod chessboard;
+pub mod goal;
 pub mod piece_selection;
+pub mod rules;
 pub mod solution;
+pub mod stats;
 pub mod step_arrows;
 pub mod step_colors;
+pub mod strategy;
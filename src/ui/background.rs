@@ -0,0 +1,21 @@
+/// Hands control back to the browser so it can repaint and handle input before a long-running
+/// task carries on.
+#[cfg(target_arch = "wasm32")]
+pub async fn yield_now() {
+    // A zero-delay timer is a macrotask, so pending events run before the task resumes.
+    gloo_timers::future::TimeoutFuture::new(0).await;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn yield_now() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return std::task::Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    })
+    .await;
+}
@@ -0,0 +1,57 @@
+use dioxus::prelude::*;
+
+use crate::board::Shape;
+
+#[component]
+pub fn BoardSize(shape: Shape, on_change: EventHandler<Shape>) -> Element {
+    rsx! {
+        fieldset {
+            class: "board-size",
+            legend { "Board size" }
+            label {
+                "Rows "
+                input {
+                    r#type: "number",
+                    min: "{Shape::MIN}",
+                    max: "{Shape::MAX}",
+                    value: "{shape.rows}",
+                    onchange: move |e| {
+                        if let Some(shape) = e
+                            .value()
+                            .parse()
+                            .ok()
+                            .and_then(|rows| Shape::new(rows, shape.cols))
+                        {
+                            on_change.call(shape);
+                        }
+                    },
+                }
+            }
+            label {
+                "Columns "
+                input {
+                    r#type: "number",
+                    min: "{Shape::MIN}",
+                    max: "{Shape::MAX}",
+                    value: "{shape.cols}",
+                    onchange: move |e| {
+                        if let Some(shape) = e
+                            .value()
+                            .parse()
+                            .ok()
+                            .and_then(|cols| Shape::new(shape.rows, cols))
+                        {
+                            on_change.call(shape);
+                        }
+                    },
+                }
+            }
+            button {
+                r#type: "button",
+                disabled: shape == Shape::STANDARD,
+                onclick: move |_| on_change.call(Shape::STANDARD),
+                "8x8"
+            }
+        }
+    }
+}
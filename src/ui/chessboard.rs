@@ -1,22 +1,28 @@
 use dioxus::prelude::*;
 
-use crate::board::Board;
+use crate::board::{Board, STRIDE};
+use crate::piece::Piece;
 
 #[component]
 pub fn Chessboard(
     board: Signal<Board>,
+    goal_square: Option<(usize, usize)>,
     on_square_click: EventHandler<(usize, usize)>,
     on_square_right_click: EventHandler<(usize, usize)>,
 ) -> Element {
+    let shape = board.read().shape();
+    let (rows, cols) = (shape.rows, shape.cols);
     rsx! {
         div {
             class: "chessboard",
-            for i in 0..64 {
+            style: "--rows: {rows}; --cols: {cols};",
+            for i in 0..rows * cols {
                 Square {
-                    key: "{i / 8}-{i % 8}",
+                    key: "{i / cols}-{i % cols}",
                     board,
-                    r: i / 8,
-                    c: i % 8,
+                    r: i / cols,
+                    c: i % cols,
+                    is_goal: goal_square == Some((i / cols, i % cols)),
                     on_square_click,
                     on_square_right_click,
                 }
@@ -30,18 +36,39 @@ fn Square(
     board: Signal<Board>,
     r: usize,
     c: usize,
+    is_goal: bool,
     on_square_click: EventHandler<(usize, usize)>,
     on_square_right_click: EventHandler<(usize, usize)>,
 ) -> Element {
+    let shade = if (r + c) % 2 == 0 { "light" } else { "dark" };
+    let goal = if is_goal { " goal" } else { "" };
     let cell = board.read().get_cell(r, c);
-    let rank_label = 8 - r;
+    let rows = board.read().shape().rows;
+    let rank_label = rows - r;
     let file_label = (b'a' + c as u8) as char;
 
     rsx! {
         div {
-            class: if (r + c) % 2 == 0 { "square light" } else { "square dark" },
+            class: "square {shade}{goal}",
 
-            onclick: move |_| on_square_click.call((r, c)),
+            onclick: move |e| {
+                if e.modifiers().contains(Modifiers::SHIFT) {
+                    cycle_budget(board, r, c, 1);
+                } else {
+                    on_square_click.call((r, c));
+                }
+            },
+
+            onwheel: move |e| {
+                if cell.is_none() {
+                    return;
+                }
+                e.prevent_default();
+                let dy = e.delta().strip_units().y;
+                if dy != 0.0 {
+                    cycle_budget(board, r, c, if dy < 0.0 { 1 } else { -1 });
+                }
+            },
 
             oncontextmenu: move |e| {
                 e.prevent_default();
@@ -55,16 +82,16 @@ fn Square(
                 let Some(f_idx) = e
                     .data_transfer()
                     .get_data("text/plain")
-                    .and_then(|s| Some(s.parse::<usize>().ok()?))
+                    .and_then(|s| s.parse::<usize>().ok())
                 else {
                     return;
                 };
 
                 let mut b = board.write();
-                if f_idx >= 64 { return; }
+                let fr = f_idx / STRIDE;
+                let fc = f_idx % STRIDE;
+                if !b.shape().contains(fr, fc) { return; }
 
-                let fr = f_idx / 8;
-                let fc = f_idx % 8;
                 if b.get_cell(fr, fc).is_some() {
                     b.move_piece(fr, fc, r, c);
                 }
@@ -74,21 +101,38 @@ fn Square(
                 span { class: "square-label rank-label", "{rank_label}" }
             }
 
-            if r == 7 {
+            if r == rows - 1 {
                 span { class: "square-label file-label", "{file_label}" }
             }
 
             if let Some(piece) = cell {
+                span {
+                    class: "move-badge",
+                    if piece.is_unlimited() { "∞" } else { "{piece.move_left()}" }
+                }
                 img {
                     src: piece.get_icon(),
                     alt: piece.to_string(),
                     class: "piece",
                     draggable: "true",
                     ondragstart: move |e| {
-                        let _ = e.data_transfer().set_data("text/plain", &(r * 8 + c).to_string());
+                        let _ = e.data_transfer().set_data("text/plain", &(r * STRIDE + c).to_string());
                     },
                 }
             }
         }
     }
 }
+
+/// Steps the capture budget of the piece on `(r, c)` through 0 to [`Piece::MAX_MOVE_LEFT`],
+/// wrapping around at either end.
+fn cycle_budget(mut board: Signal<Board>, r: usize, c: usize, step: isize) {
+    let mut b = board.write();
+    let Some(piece) = b.get_cell(r, c) else {
+        return;
+    };
+    let slots = Piece::MAX_MOVE_LEFT as isize + 1;
+    let current = piece.move_left().min(Piece::MAX_MOVE_LEFT) as isize;
+    let next = (current + step).rem_euclid(slots) as usize;
+    b.set_cell(r, c, Piece::with_moves(piece.piece_type, next));
+}
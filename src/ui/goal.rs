@@ -0,0 +1,59 @@
+use dioxus::prelude::*;
+
+use crate::piece::PieceType;
+use crate::rules::Goal;
+use crate::step::square_name;
+
+#[component]
+pub fn GoalSettings(
+    goal: Goal,
+    rows: usize,
+    picking: Signal<bool>,
+    on_change: EventHandler<Goal>,
+) -> Element {
+    let square = goal
+        .square
+        .map_or("any square".to_string(), |(r, c)| square_name(r, c, rows));
+    rsx! {
+        fieldset {
+            class: "goal-settings",
+            legend { "Goal" }
+            label {
+                "Finish with "
+                select {
+                    onchange: move |e| {
+                        let piece_type = e
+                            .value()
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|i| PieceType::ALL.get(i).copied());
+                        on_change.call(Goal { piece_type, ..goal });
+                    },
+                    option { value: "any", selected: goal.piece_type.is_none(), "any piece" }
+                    for (idx, piece_type) in PieceType::ALL.iter().enumerate() {
+                        option {
+                            value: "{idx}",
+                            selected: goal.piece_type == Some(*piece_type),
+                            "{piece_type:?}"
+                        }
+                    }
+                }
+            }
+            span { " on {square} " }
+            button {
+                class: if picking() { "goal-pick selected" } else { "goal-pick" },
+                r#type: "button",
+                aria_pressed: picking(),
+                onclick: move |_| picking.set(!picking()),
+                if picking() { "Click a square…" } else { "Pick square" }
+            }
+            if goal.square.is_some() {
+                button {
+                    r#type: "button",
+                    onclick: move |_| on_change.call(Goal { square: None, ..goal }),
+                    "Any square"
+                }
+            }
+        }
+    }
+}
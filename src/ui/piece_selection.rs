@@ -8,6 +8,7 @@ pub fn PieceSelectionBoard(selected: Signal<Option<usize>>) -> Element {
         div {
             class: "piece-selection",
             p { "Select the piece to place on the board. Right-click to clear it." }
+            p { "Scroll over a placed piece, or Shift-click it, to change how many captures it has left." }
             div {
                 class: "piece-toggle-group",
                 for (idx, piece) in PieceType::ALL.iter().enumerate() {
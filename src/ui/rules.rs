@@ -0,0 +1,74 @@
+use dioxus::prelude::*;
+
+use crate::rules::{PawnDirection, Rules};
+
+#[component]
+pub fn RulesSettings(rules: Rules, on_change: EventHandler<Rules>) -> Element {
+    rsx! {
+        fieldset {
+            class: "rules-settings",
+            legend { "Rule variant" }
+            label {
+                "Captures per piece "
+                select {
+                    onchange: move |e| {
+                        if let Some(&capture_limit) = e
+                            .value()
+                            .parse::<usize>()
+                            .ok()
+                            .and_then(|i| Rules::CAPTURE_LIMITS.get(i))
+                        {
+                            on_change.call(Rules { capture_limit, ..rules });
+                        }
+                    },
+                    for (idx, limit) in Rules::CAPTURE_LIMITS.into_iter().enumerate() {
+                        option {
+                            value: "{idx}",
+                            selected: rules.capture_limit == limit,
+                            {limit.map_or("Unlimited".to_string(), |n| n.to_string())}
+                        }
+                    }
+                }
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: rules.king_must_survive,
+                    onchange: move |e| on_change.call(Rules { king_must_survive: e.checked(), ..rules }),
+                }
+                "A King must be the last piece"
+            }
+            label {
+                input {
+                    r#type: "checkbox",
+                    checked: rules.kings_capturable,
+                    onchange: move |e| on_change.call(Rules { kings_capturable: e.checked(), ..rules }),
+                }
+                "Kings can be captured"
+            }
+            label {
+                "Pawns capture "
+                select {
+                    onchange: move |e| {
+                        let pawn_direction = if e.value() == "down" {
+                            PawnDirection::Down
+                        } else {
+                            PawnDirection::Up
+                        };
+                        on_change.call(Rules { pawn_direction, ..rules });
+                    },
+                    option {
+                        value: "up",
+                        selected: rules.pawn_direction == PawnDirection::Up,
+                        "up the board"
+                    }
+                    option {
+                        value: "down",
+                        selected: rules.pawn_direction == PawnDirection::Down,
+                        "down the board"
+                    }
+                }
+            }
+        }
+    }
+}
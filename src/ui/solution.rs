@@ -1,11 +1,127 @@
 use dioxus::prelude::*;
 
-use crate::step::Step;
+use crate::solver::best_effort::BestEffort;
+use crate::solver::{InvalidPosition, Progress, SolveOutcome};
+use crate::step::{Step, square_name};
 use crate::ui::step_colors::STEP_COLORS;
 
 #[component]
-pub fn Solution(steps: Vec<Step>, selected_step: Signal<Option<usize>>) -> Element {
-    let sans = to_sans(&steps);
+pub fn Solution(
+    outcome: SolveOutcome,
+    best_effort: Option<BestEffort>,
+    rows: usize,
+    progress: Option<Progress>,
+    selected_step: Signal<Option<usize>>,
+    hint_only: Signal<bool>,
+    on_cancel: EventHandler<()>,
+) -> Element {
+    rsx! {
+        if let Some(Progress { nodes, depth, dead_positions }) = progress {
+            div {
+                class: "solving",
+                span { "Solving… {nodes} positions searched, depth {depth}, {dead_positions} dead positions remembered." }
+                button {
+                    class: "cancel-button",
+                    r#type: "button",
+                    onclick: move |_| on_cancel.call(()),
+                    "Cancel"
+                }
+            }
+        }
+        div {
+            class: if progress.is_some() { "solution stale" } else { "solution" },
+            Outcome { outcome, best_effort, rows, selected_step, hint_only }
+        }
+    }
+}
+
+#[component]
+fn Outcome(
+    outcome: SolveOutcome,
+    best_effort: Option<BestEffort>,
+    rows: usize,
+    selected_step: Signal<Option<usize>>,
+    hint_only: Signal<bool>,
+) -> Element {
+    let steps = match outcome {
+        SolveOutcome::Solved(steps) => steps,
+        SolveOutcome::AlreadySolved => {
+            return rsx! {
+                p { class: "solution-message", "Only one piece is left, so the puzzle is already solved." }
+            };
+        }
+        SolveOutcome::Unsolvable => {
+            let Some(best) = best_effort else {
+                return rsx! {
+                    p { class: "solution-message", "No capture sequence leaves a single piece." }
+                };
+            };
+            let so_far = if best.proven { "" } else { " so far" };
+            let left = match best.pieces_left {
+                1 if !best.goal_met => "1 piece, which misses the goal".to_string(),
+                1 => "1 piece, but not the King".to_string(),
+                n => format!("{n} pieces"),
+            };
+            return rsx! {
+                p {
+                    class: "solution-message best-effort",
+                    "No complete solution, best{so_far} leaves {left}."
+                }
+                if !best.steps.is_empty() {
+                    Steps { steps: best.steps, rows, selected_step }
+                }
+            };
+        }
+        SolveOutcome::InvalidPosition(InvalidPosition::EmptyBoard) => {
+            return rsx! {
+                p { class: "solution-message", "Place some pieces on the board to solve the puzzle." }
+            };
+        }
+        SolveOutcome::InvalidPosition(reason) => {
+            return rsx! {
+                p { class: "solution-message", "Invalid position: {reason}." }
+            };
+        }
+        SolveOutcome::Aborted { nodes_searched } => {
+            return rsx! {
+                p { class: "solution-message", "Stopped after searching {nodes_searched} positions." }
+            };
+        }
+    };
+
+    if hint_only() {
+        let hint = to_sans(&steps[..1], rows).remove(0);
+        return rsx! {
+            p {
+                strong { "Hint: " }
+                span { class: "step-token", style: "color: {STEP_COLORS[0]};", "{hint}" }
+            }
+            button {
+                class: "hint-button",
+                r#type: "button",
+                onclick: move |_| hint_only.set(false),
+                "Show solution"
+            }
+        };
+    }
+
+    rsx! {
+        Steps { steps, rows, selected_step }
+        button {
+            class: "hint-button",
+            r#type: "button",
+            onclick: move |_| {
+                selected_step.set(None);
+                hint_only.set(true);
+            },
+            "Hide solution"
+        }
+    }
+}
+
+#[component]
+fn Steps(steps: Vec<Step>, rows: usize, selected_step: Signal<Option<usize>>) -> Element {
+    let sans = to_sans(&steps, rows);
     let selected = selected_step();
     rsx! {
         p {
@@ -25,12 +141,10 @@ pub fn Solution(steps: Vec<Step>, selected_step: Signal<Option<usize>>) -> Eleme
     }
 }
 
-fn to_sans(steps: &[Step]) -> Vec<String> {
+/// Algebraic names of `steps` on a board with `rows` ranks.
+fn to_sans(steps: &[Step], rows: usize) -> Vec<String> {
     let mut sans_step = vec![];
 
-    let numeric_to_sans =
-        |r: usize, c: usize| -> String { format!("{}{}", (c as u8 + b'a') as char, 8 - r) };
-
     for Step {
         from: (fr, fc),
         to: (tr, tc),
@@ -39,9 +153,9 @@ fn to_sans(steps: &[Step]) -> Vec<String> {
     {
         let tmp = format!(
             "{}{}x{}",
-            piece_type.to_string(),
-            numeric_to_sans(*fr, *fc),
-            numeric_to_sans(*tr, *tc)
+            piece_type,
+            square_name(*fr, *fc, rows),
+            square_name(*tr, *tc, rows)
         );
         sans_step.push(tmp);
     }
@@ -0,0 +1,36 @@
+use dioxus::prelude::*;
+
+use crate::solver::SolveStats;
+
+#[component]
+pub fn SearchStats(stats: SolveStats) -> Element {
+    let elapsed_ms = stats.elapsed.as_secs_f64() * 1000.0;
+    rsx! {
+        details {
+            class: "search-stats",
+            summary { "Search statistics" }
+            dl {
+                dt { "Positions expanded" }
+                dd { "{stats.expanded}" }
+                dt { "Memo hits" }
+                dd { "{stats.memo_hits}" }
+                dt { "Move-budget prunes" }
+                dd { "{stats.budget_prunes}" }
+                dt { "Stranded-piece prunes" }
+                dd { "{stats.stranded_prunes}" }
+                dt { "Isolated-King prunes" }
+                dd { "{stats.king_prunes}" }
+                dt { "Split-group prunes" }
+                dd { "{stats.split_prunes}" }
+                dt { "Goal prunes" }
+                dd { "{stats.goal_prunes}" }
+                dt { "Tablebase prunes" }
+                dd { "{stats.tablebase_prunes}" }
+                dt { "Max depth" }
+                dd { "{stats.max_depth}" }
+                dt { "Elapsed" }
+                dd { "{elapsed_ms:.1} ms" }
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use dioxus::prelude::*;
 
+use crate::board::Shape;
 use crate::step::Step;
 use crate::ui::step_colors::STEP_COLORS;
 
@@ -7,7 +8,11 @@ const ARROW_HEAD_LEN: f32 = 0.35;
 const ARROW_HEAD_WIDTH: f32 = 0.45;
 
 #[component]
-pub fn StepArrows(steps: Vec<Step>, selected_step: Signal<Option<usize>>) -> Element {
+pub fn StepArrows(
+    shape: Shape,
+    steps: Vec<Step>,
+    selected_step: Signal<Option<usize>>,
+) -> Element {
     let selected = selected_step();
     let arrow_lines: Vec<(usize, f32, f32, f32, f32)> = steps
         .iter()
@@ -28,7 +33,7 @@ pub fn StepArrows(steps: Vec<Step>, selected_step: Signal<Option<usize>>) -> Ele
     rsx! {
         svg {
             class: "chessboard-overlay",
-            view_box: "0 0 8 8",
+            view_box: "0 0 {shape.cols} {shape.rows}",
             preserve_aspect_ratio: "none",
             defs {
                 for (idx, color) in STEP_COLORS.iter().enumerate() {
@@ -0,0 +1,30 @@
+use dioxus::prelude::*;
+
+use crate::solver::strategy::Strategy;
+
+#[component]
+pub fn StrategySettings(strategy: Strategy, on_change: EventHandler<Strategy>) -> Element {
+    rsx! {
+        fieldset {
+            class: "strategy-settings",
+            legend { "Search" }
+            label {
+                "Strategy "
+                select {
+                    onchange: move |e| {
+                        if let Ok(strategy) = e.value().parse() {
+                            on_change.call(strategy);
+                        }
+                    },
+                    for choice in Strategy::ALL {
+                        option {
+                            value: "{choice.name()}",
+                            selected: strategy == choice,
+                            "{choice.label()}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
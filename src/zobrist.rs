@@ -0,0 +1,39 @@
+use crate::board::SIZE;
+use crate::piece::{Piece, PieceType};
+
+const MOVE_SLOTS: usize = Piece::UNLIMITED + 1;
+
+/// One random key per piece type, remaining-move count and square. A board's key is the XOR of
+/// the keys of its pieces, so it can be updated incrementally as pieces come and go.
+static KEYS: [[[u64; SIZE]; MOVE_SLOTS]; PieceType::ALL.len()] = keys();
+
+pub fn key(p: Piece, sq: usize) -> u64 {
+    KEYS[p.piece_type as usize][p.move_left()][sq]
+}
+
+const fn keys() -> [[[u64; SIZE]; MOVE_SLOTS]; PieceType::ALL.len()] {
+    let mut table = [[[0; SIZE]; MOVE_SLOTS]; PieceType::ALL.len()];
+    let mut state = 0x5eed_c0de_u64;
+    let mut t = 0;
+    while t < PieceType::ALL.len() {
+        let mut m = 0;
+        while m < MOVE_SLOTS {
+            let mut sq = 0;
+            while sq < SIZE {
+                state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+                table[t][m][sq] = splitmix64(state);
+                sq += 1;
+            }
+            m += 1;
+        }
+        t += 1;
+    }
+    table
+}
+
+pub(crate) const fn splitmix64(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}